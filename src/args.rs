@@ -1,13 +1,124 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use structopt::StructOpt;
 
+use hantemcli::output::{self, LineEnding, Newline, OutputFormat};
+use hantemcli::templates::NameStyle;
+use hantemcli::Renderer;
+
+use crate::message::MessageFormat;
+
+/// The description of the program in the help message.
+/// It is set on the app after deriving since the doc comments of the flattened argument structs
+/// replace it otherwise.
+pub static ABOUT: &str = "Render Handlebars templates with data files.";
+
 #[derive(Debug, StructOpt)]
 pub struct Hantemcli {
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+
+    #[structopt(flatten)]
+    pub sources: Sources,
+
+    #[structopt(
+        short,
+        long,
+        parse(from_os_str),
+        help = "Write the output to a file. The path is rendered as a template with the data."
+    )]
+    pub output: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "The directory where the rendered output path must be in."
+    )]
+    pub output_root: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Compare the rendered output with the existing output files without writing and fail if they differ."
+    )]
+    pub check_output: bool,
+
+    #[structopt(
+        long,
+        help = "Print the difference between the rendered output and the existing output files."
+    )]
+    pub diff: bool,
+
+    #[structopt(flatten)]
+    pub format: FormatOptions,
+
+    #[structopt(
+        long,
+        parse(try_from_str = output::parse_mode),
+        help = "Set the permissions of the output files in octal notation (e.g., 0600)."
+    )]
+    pub mode: Option<u32>,
+
+    #[structopt(
+        long,
+        value_name = "path",
+        help = "Render the root template for each item of the array or table at the path of the data."
+    )]
+    pub each: Option<String>,
+
+    #[structopt(short, long, help = "The name of the root template to be used.")]
+    pub root: Option<String>,
+
+    #[structopt(short, long, help = "Set the renderer to be strict.")]
+    pub strict: bool,
+
+    #[structopt(long, help = "Set the program in REPL mode.")]
+    pub repl: bool,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Run the REPL commands from the file without the prompt and stop at the first error."
+    )]
+    pub script: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        help = "Print each REPL command in the standard error before running it in the script mode."
+    )]
+    pub echo: bool,
+
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Validate the data with the JSON Schema before rendering. Accepts JSON, YAML, and TOML format."
+    )]
+    pub schema: Option<PathBuf>,
+
+    #[structopt(
+        long,
+        requires = "schema",
+        help = "Fill the missing values in the data with the defaults from the schema."
+    )]
+    pub schema_defaults: bool,
+
+    #[structopt(
+        long,
+        default_value = "human",
+        possible_values = &["human", "json"],
+        help = "Set the format of the warnings and errors. The json format prints each message as a line of JSON followed by a summary."
+    )]
+    pub message_format: MessageFormat,
+}
+
+/// The template and data files given to the program.
+/// This is shared between the default rendering mode and the subcommands.
+#[derive(Debug, StructOpt)]
+pub struct Sources {
     #[structopt(
         multiple = true,
         parse(from_os_str),
-        help = "The path of the template file."
+        help = "The path of the template file or directory. A namespace can be given with NAMESPACE=PATH."
     )]
     pub templates: Vec<PathBuf>,
 
@@ -20,23 +131,205 @@ pub struct Hantemcli {
     )]
     pub data_paths: Vec<PathBuf>,
 
-    #[structopt(short, long, parse(from_os_str), help = "Write the output to a file.")]
-    pub output: Option<PathBuf>,
+    #[structopt(
+        short,
+        long = "extension",
+        help = "Set the file extension to be searched. It can be given multiple times.",
+        default_value = "hbs",
+        number_of_values = 1
+    )]
+    pub extensions: Vec<String>,
 
-    #[structopt(short, long, help = "The name of the root template to be used.")]
-    pub root: Option<String>,
+    #[structopt(
+        long,
+        value_name = "glob",
+        number_of_values = 1,
+        help = "Only search the files in the directories matching the glob pattern. It can be given multiple times."
+    )]
+    pub include: Vec<String>,
 
-    #[structopt(short, long, help = "Set the renderer to be strict.")]
-    pub strict: bool,
+    #[structopt(
+        long,
+        value_name = "glob",
+        number_of_values = 1,
+        help = "Skip the files in the directories matching the glob pattern. It can be given multiple times."
+    )]
+    pub exclude: Vec<String>,
+
+    #[structopt(long, help = "Search the hidden files and directories.")]
+    pub hidden: bool,
 
     #[structopt(
-        short,
         long,
-        help = "Set the file extension to be searched.",
-        default_value = "hbs"
+        help = "Continue with the successfully registered templates if some of the template files failed to be registered."
     )]
-    pub extension: String,
+    pub keep_going: bool,
 
-    #[structopt(long, help = "Set the program in REPL mode.")]
-    pub repl: bool,
+    #[structopt(
+        long,
+        help = "How the template names are derived from their file paths.",
+        default_value = "relative",
+        possible_values = &["relative", "stem", "path", "with-extension"]
+    )]
+    pub name_style: NameStyle,
+}
+
+/// The formatting of the rendered output.
+/// This is applied the same way for the standard output and the output files.
+#[derive(Debug, Clone, Default, StructOpt)]
+pub struct FormatOptions {
+    #[structopt(
+        long,
+        help = "Keep, add, or strip the trailing newline of the output.",
+        default_value = "keep",
+        possible_values = &["keep", "add", "strip"]
+    )]
+    pub newline: Newline,
+
+    #[structopt(
+        long,
+        help = "Convert the line endings of the output. By default, the line endings are kept.",
+        possible_values = &["lf", "crlf"]
+    )]
+    pub line_ending: Option<LineEnding>,
+
+    #[structopt(long, help = "Start the output with the UTF-8 byte order mark.")]
+    pub bom: bool,
+
+    #[structopt(long, help = "Remove the whitespace at the end of the output.")]
+    pub trim: bool,
+}
+
+impl FormatOptions {
+    pub fn output_format(&self) -> OutputFormat {
+        OutputFormat {
+            newline: self.newline,
+            line_ending: self.line_ending,
+            bom: self.bom,
+            trim: self.trim,
+        }
+    }
+}
+
+impl Hantemcli {
+    /// Create the renderer from the sources and the rendering options.
+    pub fn renderer(&self) -> Renderer {
+        let mut renderer = self
+            .sources
+            .renderer()
+            .strict(self.strict)
+            .schema_defaults(self.schema_defaults)
+            .output_format(self.format.output_format());
+
+        if let Some(root) = &self.root {
+            renderer = renderer.root(root);
+        }
+
+        if let Some(schema) = &self.schema {
+            renderer = renderer.schema(schema);
+        }
+
+        renderer
+    }
+}
+
+impl Sources {
+    /// Create the renderer with the templates, the data files, and the environment variables.
+    pub fn renderer(&self) -> Renderer {
+        let mut renderer = Renderer::new()
+            .hidden(self.hidden)
+            .name_style(self.name_style)
+            .keep_going(self.keep_going);
+
+        for template in self.templates.iter() {
+            renderer = renderer.template_path(template);
+        }
+
+        for extension in self.extensions.iter() {
+            renderer = renderer.extension(extension);
+        }
+
+        for pattern in self.include.iter() {
+            renderer = renderer.include(pattern);
+        }
+
+        for pattern in self.exclude.iter() {
+            renderer = renderer.exclude(pattern);
+        }
+
+        for data_path in self.data_paths.iter() {
+            renderer = renderer.data_file(data_path);
+        }
+
+        renderer.env()
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Print the partial dependency graph of the templates.
+    Graph {
+        #[structopt(flatten)]
+        sources: Sources,
+
+        #[structopt(
+            short,
+            long,
+            help = "Only include the templates reachable from the given template."
+        )]
+        root: Option<String>,
+
+        #[structopt(
+            short,
+            long,
+            help = "The format of the graph.",
+            default_value = "dot",
+            possible_values = &["dot", "json"]
+        )]
+        format: GraphFormat,
+    },
+
+    /// Validate the templates and the data without rendering.
+    Check {
+        #[structopt(flatten)]
+        sources: Sources,
+
+        #[structopt(
+            short,
+            long,
+            help = "The name of the root template to be checked. By default, all of the templates not used as a partial are checked."
+        )]
+        root: Option<String>,
+    },
+
+    /// List the variables referenced by the templates compared with the keys of the data.
+    Report {
+        #[structopt(flatten)]
+        sources: Sources,
+
+        #[structopt(
+            short,
+            long,
+            help = "The name of the root template to be analyzed. By default, all of the templates not used as a partial are analyzed."
+        )]
+        root: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("No such graph format as {:?}", string)),
+        }
+    }
 }
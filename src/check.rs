@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::args::Sources;
+use hantemcli::data;
+use hantemcli::diagnostic::{Diagnostic, Severity};
+use hantemcli::graph::PartialGraph;
+use hantemcli::templates::FrontMatter;
+use hantemcli::variables;
+
+/// The kind of problem found while checking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FindingKind {
+    InvalidGlob,
+    Registration,
+    MissingPartial,
+    PartialCycle,
+    DataLoad,
+    InvalidData,
+    TemplateNotFound,
+    MissingKeys,
+    MissingVariable,
+}
+
+impl FindingKind {
+    /// The same code as the diagnostics from rendering (e.g., `missing-variable`).
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::InvalidGlob => "invalid-glob",
+            Self::Registration => "template-registration",
+            Self::MissingPartial => "missing-partial",
+            Self::PartialCycle => "partial-cycle",
+            Self::DataLoad => "data-load",
+            Self::InvalidData => "invalid-data",
+            Self::TemplateNotFound => "template-not-found",
+            Self::MissingKeys => "missing-keys",
+            Self::MissingVariable => "missing-variable",
+        }
+    }
+
+    /// Cycles between the partials are only warnings since recursive partials are valid.
+    pub fn severity(self) -> Severity {
+        match self {
+            Self::PartialCycle => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A problem found while checking the templates and the data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub kind: FindingKind,
+
+    /// The name of the template with the problem, if any.
+    pub template: Option<String>,
+
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        if self.kind.severity() == Severity::Warning {
+            write!(f, "warning: ")?;
+        }
+
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+
+            if let Some(line) = self.line {
+                write!(f, "{}:", line)?;
+            }
+
+            if let Some(column) = self.column {
+                write!(f, "{}:", column)?;
+            }
+
+            write!(f, " ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Finding {
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            severity: self.kind.severity(),
+            code: self.kind.code(),
+            message: self.message.to_string(),
+            template: self.template.clone(),
+            path: self.file.clone(),
+            line: self.line,
+            column: self.column,
+            ..Default::default()
+        }
+    }
+}
+
+/// Validate the templates and the data without rendering.
+///
+/// This parses all of the templates, resolves their partials, loads all of the data files, and
+/// checks the variable paths referenced from the root templates exist in the merged data.
+/// If no root is given, all of the templates that are not used or marked as a partial are checked.
+pub fn check(
+    sources: Sources,
+    root: Option<String>,
+) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut template_paths: HashMap<String, PathBuf> = HashMap::new();
+    let mut front_matters: HashMap<String, FrontMatter> = HashMap::new();
+
+    // Every template file is attempted to find all of the failures.
+    let renderer = sources.renderer().keep_going(true);
+    let (template_registry, registration) = match renderer.register_templates() {
+        Ok(v) => v,
+        Err(e) => {
+            findings.push(Finding {
+                kind: FindingKind::InvalidGlob,
+                template: None,
+                file: None,
+                line: None,
+                column: None,
+                message: e.to_string(),
+            });
+            return findings;
+        }
+    };
+
+    for registered_template in registration.templates {
+        front_matters.insert(
+            registered_template.name.to_string(),
+            registered_template.front_matter,
+        );
+        template_paths.insert(registered_template.name, registered_template.path);
+    }
+
+    for failure in registration.failures {
+        findings.push(Finding {
+            kind: FindingKind::Registration,
+            template: None,
+            file: Some(failure.path),
+            line: failure.line,
+            column: failure.column,
+            message: failure.message,
+        });
+    }
+
+    let partial_graph = PartialGraph::from_registry(&template_registry);
+    for (name, reference) in partial_graph.missing() {
+        findings.push(Finding {
+            kind: FindingKind::MissingPartial,
+            template: Some(name.to_string()),
+            file: template_paths.get(name).cloned(),
+            line: reference.position.map(|(line, _)| line),
+            column: reference.position.map(|(_, column)| column),
+            message: format!("The partial {:?} is not in the registry.", reference.name),
+        });
+    }
+
+    for cycle in partial_graph.cycles() {
+        findings.push(Finding {
+            kind: FindingKind::PartialCycle,
+            template: Some(cycle[0].to_string()),
+            file: template_paths.get(&cycle[0]).cloned(),
+            line: None,
+            column: None,
+            message: format!(
+                "There's a cycle between the partials: {}",
+                cycle.join(" -> ")
+            ),
+        });
+    }
+
+    // The data files that failed to be loaded are skipped and reported.
+    let data_error = |error: hantemcli::Error| {
+        let (file, message) = match error {
+            hantemcli::Error::Data {
+                path: Some(path),
+                source,
+            } => (Some(path), source.to_string()),
+            e => (None, e.to_string()),
+        };
+
+        Finding {
+            kind: FindingKind::DataLoad,
+            template: None,
+            file,
+            line: None,
+            column: None,
+            message,
+        }
+    };
+    let data = match renderer.merge_data() {
+        Ok((data, skipped_data)) => {
+            findings.extend(skipped_data.into_iter().map(data_error));
+            data.try_into()
+                .map_err(|source| hantemcli::Error::Data { path: None, source })
+        }
+        Err(e) => Err(e),
+    };
+    let data: toml::Value = match data {
+        Ok(v) => v,
+        Err(e) => {
+            findings.push(data_error(e));
+            return findings;
+        }
+    };
+
+    let roots: Vec<String> = match root {
+        Some(v) => {
+            if !template_registry.has_template(&v) {
+                findings.push(Finding {
+                    kind: FindingKind::TemplateNotFound,
+                    template: None,
+                    file: None,
+                    line: None,
+                    column: None,
+                    message: format!("There's no template named {:?} in the registry.", v),
+                });
+            }
+
+            vec![v]
+        }
+        None => partial_graph
+            .roots()
+            .into_iter()
+            .filter(|name| !front_matters.get(*name).is_some_and(|f| f.partial))
+            .map(|v| v.to_string())
+            .collect(),
+    };
+
+    let mut reported = vec![];
+    for root in roots.iter() {
+        let front_matter = front_matters.get(root).cloned().unwrap_or_default();
+        let mut data = data.clone();
+        if let Some(defaults) = &front_matter.data {
+            match toml::Value::try_from(defaults) {
+                Ok(v) => data::merge_defaults(&mut data, &v),
+                Err(e) => findings.push(Finding {
+                    kind: FindingKind::InvalidData,
+                    template: Some(root.to_string()),
+                    file: template_paths.get(root).cloned(),
+                    line: None,
+                    column: None,
+                    message: format!("The default data in the front matter cannot be used: {}", e),
+                }),
+            }
+        }
+
+        for key in front_matter.required.iter() {
+            if !data::contains_path(&data, &key.split('.').collect::<Vec<&str>>()) {
+                findings.push(Finding {
+                    kind: FindingKind::MissingKeys,
+                    template: Some(root.to_string()),
+                    file: template_paths.get(root).cloned(),
+                    line: None,
+                    column: None,
+                    message: format!("The required key `{}` is not in the data.", key),
+                });
+            }
+        }
+
+        for reference in variables::variable_references(&template_registry, root) {
+            if !reference.is_missing(&data) {
+                continue;
+            }
+
+            let finding = Finding {
+                kind: FindingKind::MissingVariable,
+                template: Some(reference.template.to_string()),
+                file: template_paths.get(&reference.template).cloned(),
+                line: reference.position.map(|(line, _)| line),
+                column: reference.position.map(|(_, column)| column),
+                message: format!(
+                    "The variable `{}` is not in the data (rendered from the root template {:?}).",
+                    reference.dotted_path(),
+                    root
+                ),
+            };
+
+            // The same partial can be rendered from multiple roots.
+            let key = (finding.file.clone(), finding.line, finding.column);
+            if !reported.contains(&key) {
+                reported.push(key);
+                findings.push(finding);
+            }
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_finds_missing_variables_test() {
+        let sources = Sources {
+            templates: vec![PathBuf::from("tests/template.hbs")],
+            data_paths: vec![PathBuf::from("tests/dev.toml")],
+            extensions: vec!["hbs".to_string()],
+            include: vec![],
+            exclude: vec![],
+            hidden: false,
+            keep_going: false,
+            name_style: Default::default(),
+        };
+
+        let findings = check(sources, None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, Some(PathBuf::from("tests/template.hbs")));
+        assert_eq!(findings[0].line, Some(1));
+        assert!(findings[0].message.contains("database.url"));
+
+        let diagnostic = findings[0].diagnostic();
+        assert_eq!(diagnostic.code, "missing-variable");
+        assert_eq!(diagnostic.template, Some("template".to_string()));
+    }
+
+    #[test]
+    fn check_recursive_partial_test() {
+        let sources = Sources {
+            templates: vec![PathBuf::from("tests/")],
+            data_paths: vec![PathBuf::from("tests/default.toml")],
+            extensions: vec!["hbs".to_string()],
+            include: vec![],
+            exclude: vec![],
+            hidden: false,
+            keep_going: false,
+            name_style: Default::default(),
+        };
+
+        // The recursive partial in `tests/base.hbs` is only a warning.
+        let findings = check(sources, None);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].kind, FindingKind::PartialCycle);
+        assert_eq!(findings[0].diagnostic().severity, Severity::Warning);
+
+        // The data files that failed to be loaded are reported with the rest of the findings.
+        let sources = Sources {
+            templates: vec![PathBuf::from("tests/")],
+            data_paths: vec![PathBuf::from("tests/ghost.toml")],
+            extensions: vec!["hbs".to_string()],
+            include: vec![],
+            exclude: vec![],
+            hidden: false,
+            keep_going: false,
+            name_style: Default::default(),
+        };
+        let findings = check(sources, Some("template".to_string()));
+        assert_eq!(findings[1].kind, FindingKind::DataLoad);
+        assert_eq!(findings[1].file, Some(PathBuf::from("tests/ghost.toml")));
+        assert_eq!(findings[2].kind, FindingKind::MissingVariable);
+    }
+}
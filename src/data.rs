@@ -0,0 +1,318 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+
+/// The path segment for any item of an array or any value of a table.
+pub static ANY_ITEM: &str = "[]";
+
+/// Load a single data file.
+/// The format of the file is detected from its file extension.
+pub fn load_data_file(
+    data_path: &Path,
+    required: bool,
+) -> Result<config::Config, config::ConfigError> {
+    let mut data = config::Config::new();
+    data.merge(config::File::with_name(&data_path.to_string_lossy()).required(required))?;
+
+    Ok(data)
+}
+
+/// A source of the data.
+/// The layers are merged in order with the later layers overriding the earlier ones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataLayer {
+    /// A data file with its format detected from the file extension.
+    File(PathBuf),
+
+    /// The data given as a value (e.g., from the code).
+    Value(toml::Value),
+
+    /// The environment variables with the prefix, if any, removed from their names.
+    /// The names are converted into lowercase.
+    Environment { prefix: Option<String> },
+}
+
+impl DataLayer {
+    /// Load the data of the layer by itself.
+    pub fn load(&self) -> Result<config::Config, config::ConfigError> {
+        match self {
+            Self::File(path) => load_data_file(path, true),
+            Self::Value(value) => config::Config::try_from(value),
+            Self::Environment { prefix } => {
+                let environment = match prefix {
+                    Some(prefix) => config::Environment::with_prefix(prefix),
+                    None => config::Environment::new(),
+                };
+
+                let mut data = config::Config::new();
+                data.merge(environment)?;
+
+                Ok(data)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DataLayer {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{:?}", path),
+            Self::Value(_) => write!(f, "the data value"),
+            Self::Environment { prefix: None } => write!(f, "the environment variables"),
+            Self::Environment {
+                prefix: Some(prefix),
+            } => write!(f, "the environment variables with the prefix {:?}", prefix),
+        }
+    }
+}
+
+/// Merge the data layers in the given order.
+/// Unless the data files are required, the data files that failed to be loaded are skipped and
+/// their errors are returned with the merged data.
+pub fn merge_layers(
+    layers: &[DataLayer],
+    require_files: bool,
+) -> Result<(config::Config, Vec<Error>), Error> {
+    let mut raw_config = config::Config::new();
+    let skipped = merge_layers_into(&mut raw_config, layers, require_files)?;
+
+    Ok((raw_config, skipped))
+}
+
+/// Merge the data layers on top of the already merged data the same as `merge_layers`.
+/// Returns the errors of the data files skipped.
+pub fn merge_layers_into(
+    raw_config: &mut config::Config,
+    layers: &[DataLayer],
+    require_files: bool,
+) -> Result<Vec<Error>, Error> {
+    let mut skipped = vec![];
+
+    for layer in layers.iter() {
+        let path = match layer {
+            DataLayer::File(path) => Some(path.to_path_buf()),
+            _ => None,
+        };
+
+        match layer
+            .load()
+            .and_then(|v| raw_config.merge(v).map(|_| ()))
+            .map_err(|source| Error::Data { path, source })
+        {
+            Ok(_v) => (),
+            Err(e @ Error::Data { path: Some(_), .. }) if !require_files => skipped.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(skipped)
+}
+
+/// Merge the default values beneath the data.
+/// Tables are merged recursively with the values from the data taking precedence.
+pub fn merge_defaults(
+    data: &mut toml::Value,
+    defaults: &toml::Value,
+) {
+    if let (toml::Value::Table(table), toml::Value::Table(default_table)) = (data, defaults) {
+        for (key, default) in default_table.iter() {
+            match table.get_mut(key) {
+                Some(value) => merge_defaults(value, default),
+                None => {
+                    table.insert(key.to_string(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Load each of the layers by itself to find the sources of the values with `value_source`.
+/// The layers that fail to be loaded are skipped since they supply no value.
+pub fn load_layers(layers: &[DataLayer]) -> Vec<(&DataLayer, toml::Value)> {
+    layers
+        .iter()
+        .filter_map(|layer| {
+            let value = layer.load().and_then(|v| v.try_into::<toml::Value>());
+            value.ok().map(|v| (layer, v))
+        })
+        .collect()
+}
+
+/// Find the data layer that supplied the value at the path in the merged data.
+/// The layers are searched in reverse order since the later layers override the earlier ones.
+pub fn value_source<'a, S: AsRef<str>>(
+    loaded_layers: &[(&'a DataLayer, toml::Value)],
+    path: &[S],
+) -> Option<&'a DataLayer> {
+    // The root of the data is supplied by all of the layers.
+    if path.is_empty() {
+        return None;
+    }
+
+    loaded_layers
+        .iter()
+        .rev()
+        .find(|(_, value)| lookup(value, path).is_some())
+        .map(|(layer, _)| *layer)
+}
+
+/// Parse the path of a value in the data into its segments.
+/// The keys are separated by dots and the indices of the arrays are in brackets (e.g.,
+/// `services[0].name`).
+pub fn parse_path(path: &str) -> Result<Vec<String>, String> {
+    let mut segments = vec![];
+
+    for part in path.split('.') {
+        let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() {
+            return Err(format!("The path {:?} has an empty key.", path));
+        }
+        segments.push(key.to_string());
+
+        while !indices.is_empty() {
+            let (index, rest) = indices
+                .strip_prefix('[')
+                .and_then(|v| v.split_once(']'))
+                .ok_or_else(|| format!("The path {:?} has an unclosed bracket.", path))?;
+            if index.parse::<usize>().is_err() {
+                return Err(format!(
+                    "The index {:?} in the path {:?} is not a number.",
+                    index, path
+                ));
+            }
+
+            segments.push(index.to_string());
+            indices = rest;
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Get the value from the data with the path segments.
+/// Each segment is a key of a table or an index of an array.
+pub fn lookup<'a, S: AsRef<str>>(
+    data: &'a toml::Value,
+    path: &[S],
+) -> Option<&'a toml::Value> {
+    path.iter().try_fold(data, |value, segment| {
+        let segment = segment.as_ref();
+        match value {
+            toml::Value::Table(table) => table.get(segment),
+            toml::Value::Array(array) => segment.parse::<usize>().ok().and_then(|i| array.get(i)),
+            _ => None,
+        }
+    })
+}
+
+/// Check if the data has a value at the path.
+/// An `ANY_ITEM` segment requires the rest of the path to be in every item of the array or table.
+pub fn contains_path<S: AsRef<str>>(
+    data: &toml::Value,
+    path: &[S],
+) -> bool {
+    let (segment, rest) = match path.split_first() {
+        Some((segment, rest)) => (segment.as_ref(), rest),
+        None => return true,
+    };
+
+    match data {
+        toml::Value::Table(table) if segment == ANY_ITEM => {
+            table.values().all(|v| contains_path(v, rest))
+        }
+        toml::Value::Array(array) if segment == ANY_ITEM => {
+            array.iter().all(|v| contains_path(v, rest))
+        }
+        _ => lookup(data, &[segment]).is_some_and(|v| contains_path(v, rest)),
+    }
+}
+
+/// Get the paths of the values that are not a table or an array in the data.
+/// The items of the arrays are merged into one path with the `ANY_ITEM` segment.
+pub fn leaf_paths(data: &toml::Value) -> BTreeSet<Vec<String>> {
+    let mut paths = BTreeSet::new();
+    collect_leaf_paths(data, &mut vec![], &mut paths);
+    paths
+}
+
+fn collect_leaf_paths(
+    data: &toml::Value,
+    path: &mut Vec<String>,
+    paths: &mut BTreeSet<Vec<String>>,
+) {
+    match data {
+        toml::Value::Table(table) if !table.is_empty() => {
+            for (key, value) in table.iter() {
+                path.push(key.to_string());
+                collect_leaf_paths(value, path, paths);
+                path.pop();
+            }
+        }
+        toml::Value::Array(array) if !array.is_empty() => {
+            path.push(ANY_ITEM.to_string());
+            for value in array.iter() {
+                collect_leaf_paths(value, path, paths);
+            }
+            path.pop();
+        }
+        _ => {
+            paths.insert(path.to_vec());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_path_test() {
+        assert_eq!(
+            parse_path("services[0].ports[1]").unwrap(),
+            vec!["services", "0", "ports", "1"]
+        );
+        assert_eq!(parse_path("database.url").unwrap(), vec!["database", "url"]);
+        assert!(parse_path("database..url").is_err());
+        assert!(parse_path("services[first]").is_err());
+        assert!(parse_path("services[0").is_err());
+
+        let data: toml::Value =
+            toml::from_str("[[services]]\nname = 'api'\nports = [80, 443]").unwrap();
+        assert_eq!(
+            lookup(&data, &parse_path("services[0].ports[1]").unwrap()),
+            Some(&toml::Value::Integer(443))
+        );
+    }
+
+    #[test]
+    fn value_source_test() {
+        std::env::set_var("HANTEMCLI_SOURCE_TEST_TOKEN", "secret");
+        let layers = vec![
+            DataLayer::File(PathBuf::from("tests/ghost.toml")),
+            DataLayer::File(PathBuf::from("tests/default.toml")),
+            DataLayer::Value(toml::from_str("[database]\nurl = 'sqlite://'").unwrap()),
+            DataLayer::Environment {
+                prefix: Some("HANTEMCLI_SOURCE_TEST".to_string()),
+            },
+        ];
+        let loaded_layers = load_layers(&layers);
+        assert_eq!(loaded_layers.len(), 3);
+
+        assert_eq!(value_source(&loaded_layers, &["dogs"]), Some(&layers[1]));
+        assert_eq!(
+            value_source(&loaded_layers, &["database", "url"]),
+            Some(&layers[2])
+        );
+        assert_eq!(value_source(&loaded_layers, &["token"]), Some(&layers[3]));
+        assert_eq!(value_source(&loaded_layers, &["ghost"]), None);
+        assert_eq!(
+            layers[3].to_string(),
+            "the environment variables with the prefix \"HANTEMCLI_SOURCE_TEST\""
+        );
+    }
+}
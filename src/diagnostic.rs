@@ -0,0 +1,376 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data;
+use crate::templates::{self, RegisteredTemplate, RegistrationFailure};
+use crate::variables;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// An error located in a template file shown similar to the compiler errors.
+///
+/// ```text
+/// error: Variable "database.uri" not found in strict mode.
+///   --> templates/base.hbs:3:5 (template "base")
+///    |
+///  3 | url={{database.uri}}
+///    |     ^^^^^^^^^^^^^^^^
+///    = note: the full path of the variable is `database.uri`
+///    = help: did you mean `database.url`?
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// A short identifier of the kind of error (e.g., `missing-variable`).
+    pub code: &'static str,
+
+    pub message: String,
+
+    /// The name of the template in the registry.
+    pub template: Option<String>,
+
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+
+    /// The line of the template file where the error is found.
+    pub source_line: Option<String>,
+
+    /// The additional information about the error.
+    pub notes: Vec<String>,
+
+    /// The suggestion for fixing the error.
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create the diagnostic of a template file that failed to be registered.
+    pub fn from_registration_failure(failure: &RegistrationFailure) -> Self {
+        let mut diagnostic = Self {
+            code: "template-registration",
+            message: failure.message.to_string(),
+            path: Some(failure.path.to_path_buf()),
+            ..Default::default()
+        };
+        diagnostic.locate(failure.line, failure.column);
+
+        diagnostic
+    }
+
+    /// Create the diagnostic of the error from rendering the root template.
+    ///
+    /// For the missing values in strict mode, the full path of the variable is searched from the
+    /// references of the root template and the similar keys in the data are suggested.
+    pub fn from_render_error(
+        error: &handlebars::RenderError,
+        registry: &handlebars::Handlebars,
+        templates: &[RegisteredTemplate],
+        root: &str,
+        data: &toml::Value,
+    ) -> Self {
+        let mut diagnostic = Self {
+            code: "render",
+            message: error.desc.to_string(),
+            template: error.template_name.clone(),
+            path: error.template_name.as_ref().and_then(|name| {
+                templates
+                    .iter()
+                    .find(|t| &t.name == name)
+                    .map(|t| t.path.to_path_buf())
+            }),
+            ..Default::default()
+        };
+        diagnostic.locate(error.line_no, error.column_no);
+
+        let missing_path = match strict_mode_path(&error.desc) {
+            Some(v) => v,
+            None => return diagnostic,
+        };
+        diagnostic.code = "missing-variable";
+
+        let position = error.line_no.zip(error.column_no);
+        let full_path = variables::variable_references(registry, root)
+            .into_iter()
+            .find(|reference| {
+                Some(&reference.template) == error.template_name.as_ref()
+                    && position.is_some()
+                    && reference.position == position
+            })
+            .map(|reference| reference.dotted_path())
+            .unwrap_or(missing_path);
+
+        diagnostic
+            .notes
+            .push(format!("the full path of the variable is `{}`", full_path));
+        diagnostic.help =
+            similar_key(&full_path, data).map(|key| format!("did you mean `{}`?", key));
+
+        diagnostic
+    }
+
+    /// Create the diagnostics of the error without the registry.
+    /// Each of the registration failures and the schema violations has its own diagnostic.
+    pub fn from_error(error: &Error) -> Vec<Self> {
+        match error {
+            Error::Registration(e) => e
+                .failures
+                .iter()
+                .map(Self::from_registration_failure)
+                .collect(),
+            Error::SchemaViolations { path, violations } => violations
+                .iter()
+                .map(|violation| Self {
+                    code: error.code(),
+                    message: violation.to_string(),
+                    path: Some(path.to_path_buf()),
+                    ..Default::default()
+                })
+                .collect(),
+            Error::Render(e) => {
+                let mut diagnostic = Self {
+                    code: error.code(),
+                    message: e.desc.to_string(),
+                    template: e.template_name.clone(),
+                    ..Default::default()
+                };
+                diagnostic.locate(e.line_no, e.column_no);
+
+                vec![diagnostic]
+            }
+            _ => vec![Self {
+                code: error.code(),
+                message: error.to_string(),
+                path: match error {
+                    Error::Data { path, .. } => path.clone(),
+                    Error::Schema { path, .. } | Error::Output { path, .. } => {
+                        Some(path.to_path_buf())
+                    }
+                    _ => None,
+                },
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Set the position of the error with the line from the template file.
+    fn locate(
+        &mut self,
+        line: Option<usize>,
+        column: Option<usize>,
+    ) {
+        self.line = line;
+        self.column = column;
+
+        if let (Some(path), Some(line)) = (&self.path, line) {
+            if let Some((source_line, offset)) = source_line(path, line) {
+                // The first line after the front matter is shifted by its replacement.
+                self.column = column.map(|v| v.saturating_sub(offset).max(1));
+                self.source_line = Some(source_line);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        writeln!(f, "{}: {}", self.severity, self.message)?;
+
+        let gutter = " ".repeat(self.line.map(|v| v.to_string().len()).unwrap_or(1));
+        if let Some(path) = &self.path {
+            write!(f, "{}--> {}", gutter, path.display())?;
+
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+
+            if let Some(template) = &self.template {
+                write!(f, " (template {:?})", template)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        if let (Some(line), Some(source_line)) = (self.line, &self.source_line) {
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{} | {}", line, source_line)?;
+
+            if let Some(column) = self.column {
+                let start = source_line
+                    .char_indices()
+                    .nth(column.saturating_sub(1))
+                    .map(|(i, _)| i)
+                    .unwrap_or(source_line.len());
+                writeln!(
+                    f,
+                    "{} | {}{}",
+                    gutter,
+                    " ".repeat(column.saturating_sub(1)),
+                    "^".repeat(expression_width(&source_line[start..]))
+                )?;
+            }
+        }
+
+        for note in self.notes.iter() {
+            writeln!(f, "{} = note: {}", gutter, note)?;
+        }
+
+        if let Some(help) = &self.help {
+            writeln!(f, "{} = help: {}", gutter, help)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the line of the template file along with the column offset from the front matter.
+fn source_line(
+    path: &Path,
+    line: usize,
+) -> Option<(String, usize)> {
+    let source = fs::read_to_string(path).ok()?;
+    let source_line = source.lines().nth(line.checked_sub(1)?)?.to_string();
+
+    let offset = match templates::split_front_matter(&source) {
+        (Some(_front_matter), template) => template
+            .lines()
+            .nth(line - 1)
+            .map(|v| v.len().saturating_sub(source_line.len()))
+            .unwrap_or_default(),
+        _ => 0,
+    };
+
+    Some((source_line, offset))
+}
+
+/// Get the width of the expression at the start of the text (i.e., until the closing `}}`).
+fn expression_width(text: &str) -> usize {
+    match text.starts_with("{{") {
+        true => text
+            .find("}}")
+            .map(|i| text[..i + 2].chars().count())
+            .unwrap_or(1),
+        false => 1,
+    }
+}
+
+/// Get the variable path from the error of a missing value in strict mode.
+fn strict_mode_path(description: &str) -> Option<String> {
+    let path = description
+        .strip_prefix("Variable \"")?
+        .strip_suffix("\" not found in strict mode.")?;
+
+    Some(path.to_string())
+}
+
+/// Find the key in the data most similar to the given dotted path.
+fn similar_key(
+    path: &str,
+    data: &toml::Value,
+) -> Option<String> {
+    let mut keys = BTreeSet::new();
+    for leaf in data::leaf_paths(data) {
+        for length in 1..=leaf.len() {
+            keys.insert(variables::dotted_path(&leaf[..length]));
+        }
+    }
+
+    let max_distance = (path.chars().count() / 3).max(1);
+    keys.into_iter()
+        .map(|key| (edit_distance(path, &key), key))
+        .filter(|(distance, _)| *distance > 0 && *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key)
+}
+
+/// The Levenshtein distance between the two strings.
+fn edit_distance(
+    a: &str,
+    b: &str,
+) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_error_diagnostic_test() {
+        let mut registry = handlebars::Handlebars::new();
+        registry.set_strict_mode(true);
+        registry
+            .register_template_string("root", "Hello\n{{#with database}}url={{uri}}{{/with}}")
+            .unwrap();
+
+        let data: toml::Value = toml::from_str("[database]\nurl = 'postgres://'").unwrap();
+        let error = registry.render("root", &data).unwrap_err();
+
+        let diagnostic = Diagnostic::from_render_error(&error, &registry, &[], "root", &data);
+        assert_eq!(diagnostic.code, "missing-variable");
+        assert_eq!(diagnostic.template, Some("root".to_string()));
+        assert_eq!(diagnostic.line, Some(2));
+        assert_eq!(
+            diagnostic.notes,
+            vec!["the full path of the variable is `database.uri`"]
+        );
+        assert_eq!(
+            diagnostic.help,
+            Some("did you mean `database.url`?".to_string())
+        );
+    }
+
+    #[test]
+    fn zero_column_display_test() {
+        // The columns start from 1 but an invalid zero column is shown as the first one.
+        let diagnostic = Diagnostic {
+            message: "Template error".to_string(),
+            line: Some(1),
+            column: Some(0),
+            source_line: Some("{{#if}}".to_string()),
+            ..Default::default()
+        };
+
+        assert!(diagnostic.to_string().contains("1 | {{#if}}\n  | ^"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::schema::Violation;
+use crate::templates::RegistrationError;
+
+/// The errors from rendering the templates with the data.
+#[derive(Debug)]
+pub enum Error {
+    /// The include or exclude patterns of the template search are not valid.
+    Glob(globset::Error),
+
+    /// Some of the template files failed to be registered.
+    Registration(RegistrationError),
+
+    /// There's no template to be rendered.
+    NoTemplates,
+
+    /// The root template is not in the registry.
+    TemplateNotFound(String),
+
+    /// A data file (or the environment variables if there's no path) cannot be loaded or merged.
+    Data {
+        path: Option<PathBuf>,
+        source: config::ConfigError,
+    },
+
+    /// The value cannot be used as data (e.g., the default data from the front matter).
+    InvalidData(String),
+
+    /// The schema file cannot be used.
+    Schema {
+        path: PathBuf,
+        message: String,
+    },
+
+    /// The data does not satisfy the schema.
+    SchemaViolations {
+        path: PathBuf,
+        violations: Vec<Violation>,
+    },
+
+    /// The data does not have the keys required by the front matter of the root template.
+    MissingKeys {
+        template: String,
+        keys: Vec<String>,
+    },
+
+    /// The value to be iterated with `--each` is missing or not an array or a table.
+    Items(String),
+
+    /// The template failed to be rendered.
+    Render(handlebars::RenderError),
+
+    /// The output path cannot be used (e.g., outside of the output root).
+    OutputPath(String),
+
+    /// The output file cannot be read or written.
+    Output {
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    Io(io::Error),
+}
+
+impl Error {
+    /// A short identifier of the kind of error (e.g., `data-load`) for the machine-readable
+    /// messages.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Glob(_) => "invalid-glob",
+            Self::Registration(_) => "template-registration",
+            Self::NoTemplates => "no-templates",
+            Self::TemplateNotFound(_) => "template-not-found",
+            Self::Data { .. } => "data-load",
+            Self::InvalidData(_) => "invalid-data",
+            Self::Schema { .. } => "invalid-schema",
+            Self::SchemaViolations { .. } => "schema-violation",
+            Self::MissingKeys { .. } => "missing-keys",
+            Self::Items(_) => "invalid-items",
+            Self::Render(e) if e.desc.ends_with("not found in strict mode.") => "missing-variable",
+            Self::Render(_) => "render",
+            Self::OutputPath(_) => "invalid-output-path",
+            Self::Output { .. } => "output",
+            Self::Io(_) => "io",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Self::Glob(e) => write!(f, "The glob pattern is not valid: {}", e),
+            Self::Registration(e) => write!(f, "{}", e),
+            Self::NoTemplates => write!(f, "There's no templates registered in the registry."),
+            Self::TemplateNotFound(name) => {
+                write!(f, "There's no template named {:?} in the registry.", name)
+            }
+            Self::Data {
+                path: Some(path),
+                source,
+            } => write!(
+                f,
+                "An error occurred for the data file {:?}\n{}",
+                path, source
+            ),
+            Self::Data { path: None, source } => {
+                write!(f, "The merged data cannot be used: {}", source)
+            }
+            Self::InvalidData(message) => write!(f, "{}", message),
+            Self::Schema { path, message } => {
+                write!(f, "The schema {:?} cannot be used: {}", path, message)
+            }
+            Self::SchemaViolations { path, violations } => {
+                for violation in violations.iter() {
+                    writeln!(f, "{}", violation)?;
+                }
+
+                write!(
+                    f,
+                    "The data has {} violation(s) of the schema {:?}.",
+                    violations.len(),
+                    path
+                )
+            }
+            Self::MissingKeys { template, keys } => write!(
+                f,
+                "The template {:?} requires the missing key(s): {}",
+                template,
+                keys.join(", ")
+            ),
+            Self::Items(message) => write!(f, "{}", message),
+            Self::Render(e) => write!(f, "{}", e),
+            Self::OutputPath(message) => write!(f, "{}", message),
+            Self::Output { path, source } => {
+                write!(f, "Cannot use the output file {:?}: {}", path, source)
+            }
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Glob(e) => Some(e),
+            Self::Registration(e) => Some(e),
+            Self::Data { source, .. } => Some(source),
+            Self::Render(e) => Some(e),
+            Self::Output { source, .. } => Some(source),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<globset::Error> for Error {
+    fn from(error: globset::Error) -> Self {
+        Self::Glob(error)
+    }
+}
+
+impl From<RegistrationError> for Error {
+    fn from(error: RegistrationError) -> Self {
+        Self::Registration(error)
+    }
+}
+
+impl From<handlebars::RenderError> for Error {
+    fn from(error: handlebars::RenderError) -> Self {
+        Self::Render(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
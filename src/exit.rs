@@ -0,0 +1,131 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::Diagnostics;
+
+/// The exit status of the program for each class of failure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitStatus {
+    /// The templates or the output files did not pass the checks (i.e., the `check` subcommand,
+    /// `--check-output`) or any other failure.
+    Failure = 1,
+
+    /// The command line arguments are not valid.
+    Usage = 2,
+
+    /// The templates cannot be found or registered.
+    Template = 3,
+
+    /// The data cannot be loaded or does not satisfy the schema or the required keys.
+    Data = 4,
+
+    /// The template failed to be rendered (e.g., a missing value in strict mode).
+    Render = 5,
+
+    /// The output cannot be written.
+    Output = 6,
+}
+
+impl ExitStatus {
+    /// Get the exit status for the error returned by the program.
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        if let Some(failure) = error.downcast_ref::<Failure>() {
+            return failure.status;
+        }
+
+        if let Some(diagnostics) = error.downcast_ref::<Diagnostics>() {
+            return diagnostics.status;
+        }
+
+        if let Some(error) = error.downcast_ref::<hantemcli::Error>() {
+            return Self::from(error);
+        }
+
+        if error.is::<config::ConfigError>()
+            || error.is::<toml::ser::Error>()
+            || error.is::<toml::de::Error>()
+        {
+            return Self::Data;
+        }
+
+        if error.is::<handlebars::RenderError>() || error.is::<handlebars::TemplateRenderError>() {
+            return Self::Render;
+        }
+
+        // The errors from reading the input are wrapped with their own status so the rest are
+        // from writing the output.
+        if error.is::<io::Error>() {
+            return Self::Output;
+        }
+
+        Self::Failure
+    }
+
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// The name of the exit status used as the code of the machine-readable messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Failure => "failure",
+            Self::Usage => "usage",
+            Self::Template => "template",
+            Self::Data => "data",
+            Self::Render => "render",
+            Self::Output => "output",
+        }
+    }
+}
+
+impl From<&hantemcli::Error> for ExitStatus {
+    fn from(error: &hantemcli::Error) -> Self {
+        use hantemcli::Error;
+
+        match error {
+            Error::Glob(_) => Self::Usage,
+            Error::Registration(_) | Error::NoTemplates | Error::TemplateNotFound(_) => {
+                Self::Template
+            }
+            Error::Data { .. }
+            | Error::InvalidData(_)
+            | Error::Schema { .. }
+            | Error::SchemaViolations { .. }
+            | Error::MissingKeys { .. }
+            | Error::Items(_) => Self::Data,
+            Error::Render(_) => Self::Render,
+            Error::OutputPath(_) | Error::Output { .. } | Error::Io(_) => Self::Output,
+        }
+    }
+}
+
+/// An error from the program with the exit status it results in.
+#[derive(Debug)]
+pub struct Failure {
+    pub status: ExitStatus,
+    pub error: Box<dyn Error>,
+}
+
+impl Failure {
+    pub fn new<E: Into<Box<dyn Error>>>(
+        status: ExitStatus,
+        error: E,
+    ) -> Self {
+        Self {
+            status,
+            error: error.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for Failure {}
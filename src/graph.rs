@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use handlebars::template::{Parameter, Template, TemplateElement, TemplateMapping};
+use serde_json::json;
+
+/// The name of the special partial that refers to the block of a partial block.
+static PARTIAL_BLOCK_NAME: &str = "@partial-block";
+
+/// A partial referenced by a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialReference {
+    pub name: String,
+
+    /// Whether the partial is referenced as a partial block (i.e., `{{#> partial}}...{{/partial}}`).
+    /// Handlebars renders the block as a fallback if the partial is missing so it is not an error.
+    pub has_fallback: bool,
+
+    /// The line and column of the reference in the template if the source map is available.
+    pub position: Option<(usize, usize)>,
+
+    /// The inline partials defined in the block of a partial block which are available to the
+    /// partial (e.g., `{{#> layout}}{{#*inline "content"}}...{{/inline}}{{/layout}}`).
+    pub inline_partials: Vec<String>,
+}
+
+/// Extract the partials referenced by the template.
+///
+/// This includes partial expressions and partial blocks.
+/// Partials defined with inline partials (i.e., `{{#*inline "name"}}`) in the template itself are
+/// not included since they do not refer to the registry.
+/// Dynamic partials (e.g., `{{> (lookup . "name")}}`) are skipped since they can only be resolved
+/// at render time.
+pub fn partial_references(template: &Template) -> Vec<PartialReference> {
+    let mut references = vec![];
+    let mut inline_partials = BTreeSet::new();
+    collect_partial_references(template, &mut references, &mut inline_partials);
+
+    references
+        .into_iter()
+        .filter(|reference| !inline_partials.contains(&reference.name))
+        .collect()
+}
+
+fn collect_partial_references(
+    template: &Template,
+    references: &mut Vec<PartialReference>,
+    inline_partials: &mut BTreeSet<String>,
+) {
+    for (index, element) in template.elements.iter().enumerate() {
+        let position = element_position(template, index);
+
+        match element {
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                let mut block_references = vec![];
+                let mut block_inline_partials = BTreeSet::new();
+                if let Some(t) = &partial.template {
+                    collect_partial_references(
+                        t,
+                        &mut block_references,
+                        &mut block_inline_partials,
+                    );
+                }
+
+                if let Some(name) = static_name(&partial.name) {
+                    if name != PARTIAL_BLOCK_NAME {
+                        references.push(PartialReference {
+                            name,
+                            has_fallback: matches!(element, TemplateElement::PartialBlock(_)),
+                            position,
+                            inline_partials: block_inline_partials.iter().cloned().collect(),
+                        });
+                    }
+                }
+
+                references.extend(block_references);
+                inline_partials.extend(block_inline_partials);
+            }
+            TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => {
+                if static_name(&decorator.name).as_deref() == Some("inline") {
+                    if let Some(Parameter::Literal(serde_json::Value::String(name))) =
+                        decorator.params.first()
+                    {
+                        inline_partials.insert(name.to_string());
+                    }
+                }
+
+                if let Some(t) = &decorator.template {
+                    collect_partial_references(t, references, inline_partials);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                for t in helper.template.iter().chain(helper.inverse.iter()) {
+                    collect_partial_references(t, references, inline_partials);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Get the line and column of the element from the source map of the template.
+pub fn element_position(
+    template: &Template,
+    index: usize,
+) -> Option<(usize, usize)> {
+    template
+        .mapping
+        .as_ref()
+        .and_then(|mapping| mapping.get(index))
+        .map(|&TemplateMapping(line, column)| (line, column))
+}
+
+/// Get the name of a parameter if it can be known without rendering.
+pub fn static_name(parameter: &Parameter) -> Option<String> {
+    match parameter {
+        Parameter::Literal(serde_json::Value::String(name)) => Some(name.to_string()),
+        Parameter::Name(_) | Parameter::Path(_) => parameter.as_name().map(|v| v.to_string()),
+        _ => None,
+    }
+}
+
+/// The dependency graph of the templates in the registry connected by their partials.
+#[derive(Debug, Default)]
+pub struct PartialGraph {
+    /// The templates with the partials they reference.
+    pub references: BTreeMap<String, Vec<PartialReference>>,
+}
+
+impl PartialGraph {
+    pub fn from_registry(registry: &handlebars::Handlebars) -> Self {
+        let references = registry
+            .get_templates()
+            .iter()
+            .map(|(name, template)| (name.to_string(), partial_references(template)))
+            .collect();
+
+        Self { references }
+    }
+
+    /// Get the names of the partials referenced by the template.
+    pub fn dependencies(
+        &self,
+        name: &str,
+    ) -> BTreeSet<&str> {
+        self.references
+            .get(name)
+            .map(|references| references.iter().map(|r| r.name.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the references to the partials that are not in the registry.
+    /// Partial blocks are not included since they have a fallback.
+    /// The inline partials given by the callers through a partial block are not missing either.
+    pub fn missing(&self) -> Vec<(&str, &PartialReference)> {
+        let provided = self.provided_inline_partials();
+
+        self.references
+            .iter()
+            .flat_map(|(name, references)| references.iter().map(move |r| (name.as_str(), r)))
+            .filter(|(name, r)| {
+                !r.has_fallback
+                    && !self.references.contains_key(&r.name)
+                    && !provided
+                        .get(name)
+                        .map(|names| names.contains(r.name.as_str()))
+                        .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /// Get the inline partials available to each template from the partial blocks invoking it.
+    /// The inline partials are also available to the partials of the invoked template.
+    fn provided_inline_partials(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut provided: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+
+        for reference in self.references.values().flatten() {
+            if reference.inline_partials.is_empty() {
+                continue;
+            }
+
+            for name in self.reachable(&reference.name) {
+                provided
+                    .entry(name)
+                    .or_default()
+                    .extend(reference.inline_partials.iter().map(|v| v.as_str()));
+            }
+        }
+
+        provided
+    }
+
+    /// Get the names of the templates that are reachable from the given template.
+    fn reachable<'a>(
+        &'a self,
+        root: &'a str,
+    ) -> BTreeSet<&'a str> {
+        let mut visited = BTreeSet::new();
+        let mut stack = vec![root];
+
+        while let Some(name) = stack.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+
+            stack.extend(self.dependencies(name));
+        }
+
+        visited
+    }
+
+    /// Create a subgraph with only the templates that are reachable from the given template.
+    pub fn reachable_from(
+        &self,
+        root: &str,
+    ) -> Self {
+        let visited = self.reachable(root);
+        let references = self
+            .references
+            .iter()
+            .filter(|(name, _)| visited.contains(name.as_str()))
+            .map(|(name, references)| (name.to_string(), references.to_vec()))
+            .collect();
+
+        Self { references }
+    }
+
+    /// Get the templates that are not referenced by any other template.
+    pub fn roots(&self) -> Vec<&str> {
+        let referenced: BTreeSet<&str> = self
+            .references
+            .keys()
+            .flat_map(|name| self.dependencies(name))
+            .collect();
+
+        self.references
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| !referenced.contains(name))
+            .collect()
+    }
+
+    /// Find the cycles in the graph.
+    /// Each cycle is given as the list of the template names starting and ending with the same template.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = vec![];
+        let mut finished = BTreeSet::new();
+
+        for name in self.references.keys() {
+            let mut path = vec![];
+            self.find_cycles(name, &mut path, &mut finished, &mut cycles);
+        }
+
+        cycles
+    }
+
+    fn find_cycles<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        finished: &mut BTreeSet<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(start) = path.iter().position(|n| *n == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            cycles.push(cycle);
+            return;
+        }
+
+        if finished.contains(name) || !self.references.contains_key(name) {
+            return;
+        }
+
+        path.push(name);
+        for dependency in self.dependencies(name) {
+            self.find_cycles(dependency, path, finished, cycles);
+        }
+        path.pop();
+
+        finished.insert(name);
+    }
+
+    /// Render the graph in the Graphviz DOT format.
+    /// Missing partials are drawn with a dashed red edge.
+    /// The inline partials given by the callers are not drawn.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph partials {\n");
+        let missing: Vec<(&str, &PartialReference)> = self.missing();
+
+        for (name, references) in self.references.iter() {
+            writeln!(dot, "    {:?};", name).unwrap();
+
+            for reference in references {
+                let is_missing = missing
+                    .iter()
+                    .any(|(n, r)| *n == name && std::ptr::eq(*r, reference));
+                match (self.references.contains_key(&reference.name), is_missing) {
+                    (true, _) => writeln!(dot, "    {:?} -> {:?};", name, reference.name).unwrap(),
+                    (false, false) => (),
+                    (false, true) => writeln!(
+                        dot,
+                        "    {:?} -> {:?} [style=dashed, color=red];",
+                        name, reference.name
+                    )
+                    .unwrap(),
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as a JSON object.
+    pub fn to_json(&self) -> serde_json::Value {
+        let templates: serde_json::Map<String, serde_json::Value> = self
+            .references
+            .iter()
+            .map(|(name, references)| {
+                let partials: Vec<&str> = references.iter().map(|r| r.name.as_str()).collect();
+                (name.to_string(), json!({ "partials": partials }))
+            })
+            .collect();
+
+        let missing: Vec<serde_json::Value> = self
+            .missing()
+            .iter()
+            .map(|(name, reference)| {
+                json!({
+                    "template": name,
+                    "partial": reference.name,
+                    "line": reference.position.map(|(line, _)| line),
+                    "column": reference.position.map(|(_, column)| column),
+                })
+            })
+            .collect();
+
+        json!({
+            "templates": templates,
+            "missing": missing,
+            "cycles": self.cycles(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_with(templates: &[(&str, &str)]) -> handlebars::Handlebars<'static> {
+        let mut registry = handlebars::Handlebars::new();
+        for (name, source) in templates {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        registry
+    }
+
+    #[test]
+    fn partial_references_test() {
+        let template = Template::compile(
+            "{{#*inline \"local\"}}hi{{/inline}}{{> local}}{{> base}}{{#if a}}{{#> layout}}x{{/layout}}{{/if}}",
+        )
+        .unwrap();
+
+        let references: Vec<(String, bool)> = partial_references(&template)
+            .into_iter()
+            .map(|r| (r.name, r.has_fallback))
+            .collect();
+        assert_eq!(
+            references,
+            vec![("base".to_string(), false), ("layout".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn missing_and_cycles_test() {
+        let registry = registry_with(&[
+            ("a", "{{> b}}{{> ghost}}"),
+            ("b", "{{> c}}"),
+            ("c", "{{> b}}"),
+            ("d", "standalone"),
+        ]);
+        let graph = PartialGraph::from_registry(&registry);
+
+        let missing: Vec<&str> = graph
+            .missing()
+            .iter()
+            .map(|(_, r)| r.name.as_str())
+            .collect();
+        assert_eq!(missing, vec!["ghost"]);
+        assert_eq!(graph.cycles(), vec![vec!["b", "c", "b"]]);
+        assert_eq!(graph.roots(), vec!["a", "d"]);
+
+        let subgraph = graph.reachable_from("a");
+        assert_eq!(
+            subgraph.references.keys().collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn layout_inline_partials_test() {
+        let registry = registry_with(&[
+            ("layout", "<main>{{> header}}{{> content}}</main>"),
+            ("header", "<h1>{{> title}}</h1>"),
+            (
+                "page",
+                "{{#> layout}}{{#*inline \"title\"}}Hi{{/inline}}{{#*inline \"content\"}}there{{/inline}}{{/layout}}",
+            ),
+            ("other", "{{> content}}"),
+        ]);
+        assert_eq!(
+            registry.render("page", &()).unwrap(),
+            "<main><h1>Hi</h1>there</main>"
+        );
+
+        let graph = PartialGraph::from_registry(&registry);
+        let missing: Vec<(&str, &str)> = graph
+            .missing()
+            .iter()
+            .map(|(name, r)| (*name, r.name.as_str()))
+            .collect();
+        assert_eq!(missing, vec![("other", "content")]);
+        assert!(!graph.to_dot().contains("\"layout\" -> \"content\""));
+    }
+}
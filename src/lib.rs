@@ -0,0 +1,33 @@
+//! Render Handlebars templates with layered data files.
+//!
+//! This is the library behind the `hantemcli` command line application.
+//! The [`Renderer`] builder searches the templates, merges the data layers, and renders the root
+//! template the same way as the command line.
+//!
+//! ```
+//! use hantemcli::Renderer;
+//!
+//! let rendered = Renderer::new()
+//!     .template_path("tests/template.hbs")
+//!     .data_file("tests/default.toml")
+//!     .env()
+//!     .strict(true)
+//!     .render_to_string()?;
+//! # Ok::<(), hantemcli::Error>(())
+//! ```
+
+pub mod data;
+pub mod diagnostic;
+pub mod graph;
+pub mod output;
+pub mod render;
+pub mod report;
+pub mod schema;
+pub mod templates;
+pub mod variables;
+
+mod error;
+mod renderer;
+
+pub use error::Error;
+pub use renderer::{Prepared, Rendered, Renderer, Target};
@@ -1,98 +1,439 @@
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::{self, BufReader, IsTerminal};
 use std::process;
+use std::time::Instant;
 
-use config;
-use handlebars;
+use structopt::clap::ErrorKind;
 use structopt::StructOpt;
-use toml;
 
 mod args;
+mod check;
+mod exit;
+mod message;
 mod repl;
-mod templates;
+mod shell;
 
-use args::Hantemcli;
-
-static ERROR_EXIT_STATUS: i64 = 1;
+use args::{Command, GraphFormat, Hantemcli};
+use exit::{ExitStatus, Failure};
+use hantemcli::data::{self, DataLayer};
+use hantemcli::diagnostic::{Diagnostic, Severity};
+use hantemcli::output::WriteOptions;
+use hantemcli::templates::RegistrationFailure;
+use hantemcli::{graph, report};
+use message::Messages;
 
 fn main() {
-    let args = Hantemcli::from_args();
+    let args = match Hantemcli::clap().about(args::ABOUT).get_matches_safe() {
+        Ok(matches) => Hantemcli::from_clap(&matches),
+        Err(e) => match e.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => e.exit(),
+            _ => {
+                eprintln!("{}", e.message);
+                process::exit(ExitStatus::Usage.code());
+            }
+        },
+    };
+
+    let mut messages = Messages::new(args.message_format);
+    let result = parse_args(args, &mut messages);
+    let exit_status = result
+        .as_ref()
+        .err()
+        .map(|e| ExitStatus::from_error(e.as_ref()));
+
+    messages.finish(&result);
+    if let Some(exit_status) = exit_status {
+        process::exit(exit_status.code());
+    }
+}
+
+/// The errors located in the template files printed as is instead of the final error.
+#[derive(Debug)]
+pub struct Diagnostics {
+    pub status: ExitStatus,
+    pub diagnostics: Vec<Diagnostic>,
+}
 
-    match parse_args(args) {
-        Ok(_) => (),
-        Err(e) => {
-            eprintln!("Final error:\n---\n{}\n---\n", e);
-            process::exit(ERROR_EXIT_STATUS as i32);
+impl fmt::Display for Diagnostics {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        for diagnostic in self.diagnostics.iter() {
+            writeln!(f, "{}", diagnostic)?;
         }
+
+        write!(
+            f,
+            "Found {} error(s) in the templates.",
+            self.diagnostics.len()
+        )
     }
 }
 
-pub fn parse_args(args: Hantemcli) -> Result<(), Box<dyn Error>> {
-    let mut template_registry = handlebars::Handlebars::new();
-    template_registry.set_strict_mode(args.strict);
-    templates::register_from_path(&mut template_registry, args.templates, &args.extension)?;
-
-    let mut raw_config: config::Config = config::Config::new();
-    // Getting the data from the files.
-    for data_path in args.data_paths.iter() {
-        match raw_config
-            .merge(config::File::with_name(&data_path.to_string_lossy()).required(false))
-        {
-            Ok(_v) => (),
-            Err(e) => eprintln!(
-                "An error occurred for the data file {:?}\n{}\n",
-                data_path, e
-            ),
+impl Error for Diagnostics {}
+
+/// Show the template registration failures as diagnostics.
+fn diagnose(error: hantemcli::Error) -> Box<dyn Error> {
+    match error {
+        hantemcli::Error::Registration(e) => Box::new(Diagnostics {
+            status: ExitStatus::Template,
+            diagnostics: e
+                .failures
+                .iter()
+                .map(Diagnostic::from_registration_failure)
+                .collect(),
+        }),
+        e => e.into(),
+    }
+}
+
+/// Report the template files skipped with `--keep-going` as warnings.
+fn warn_skipped(
+    messages: &mut Messages,
+    failures: &[RegistrationFailure],
+) {
+    for failure in failures.iter() {
+        let mut diagnostic = Diagnostic::from_registration_failure(failure);
+        diagnostic.severity = Severity::Warning;
+        messages.diagnostic(diagnostic);
+    }
+}
+
+/// Report the data files skipped for failing to be loaded as warnings.
+fn warn_skipped_data(
+    messages: &mut Messages,
+    errors: &[hantemcli::Error],
+) {
+    for error in errors.iter() {
+        for mut diagnostic in Diagnostic::from_error(error) {
+            diagnostic.severity = Severity::Warning;
+            messages.diagnostic(diagnostic);
         }
     }
+}
 
-    // Merging the data from environment variables.
-    raw_config.merge(config::Environment::new())?;
+pub fn parse_args(
+    args: Hantemcli,
+    messages: &mut Messages,
+) -> Result<(), Box<dyn Error>> {
+    match args.command {
+        Some(Command::Graph {
+            sources,
+            root,
+            format,
+        }) => print_graph(sources, root, format, messages),
+        Some(Command::Check { sources, root }) => {
+            let findings = check::check(sources, root);
+            for finding in findings.iter() {
+                messages.report(finding, finding.diagnostic());
+            }
 
-    if args.repl {
-        let mut repl_env = repl::Repl::default();
-        repl_env.data = raw_config;
-        repl_env.template_registry = template_registry;
+            // The warnings (e.g., recursive partials) do not fail the check.
+            let errors = findings
+                .iter()
+                .filter(|f| f.kind.severity() == Severity::Error)
+                .count();
+            match errors {
+                0 => Ok(()),
+                _ => Err(Failure::new(
+                    ExitStatus::Failure,
+                    format!("Found {} problem(s).", errors),
+                )
+                .into()),
+            }
+        }
+        Some(Command::Report { sources, root }) => print_report(sources, root, messages),
+        None => render(args, messages),
+    }
+}
+
+pub fn render(
+    args: Hantemcli,
+    messages: &mut Messages,
+) -> Result<(), Box<dyn Error>> {
+    let renderer = args.renderer();
+
+    if args.repl || args.script.is_some() {
+        let (template_registry, registration) = renderer.register_templates()?;
+        warn_skipped(messages, &registration.failures);
+        let (data, skipped_data) = renderer.merge_data()?;
+        warn_skipped_data(messages, &skipped_data);
+
+        let mut repl_env = repl::Repl {
+            data,
+            template_registry,
+            template_filter: renderer.template_filter()?,
+            name_style: args.sources.name_style,
+            output_format: args.format.output_format(),
+            mode: args.mode,
+            output_root: args.output_root,
+            template_paths: renderer.template_paths().to_vec(),
+            data_layers: renderer.data_layers().to_vec(),
+            templates: registration.templates,
+            keep_going: args.sources.keep_going,
+            ..Default::default()
+        };
 
-        repl_env._loop()?;
+        // The piped standard input is run the same as a script.
+        match &args.script {
+            Some(path) => {
+                let script = File::open(path).map_err(|e| {
+                    Failure::new(
+                        ExitStatus::Usage,
+                        format!("Cannot open the script {:?}: {}", path, e),
+                    )
+                })?;
+                repl_env.run_script(BufReader::new(script), args.echo)?;
+            }
+            None if !io::stdin().is_terminal() => {
+                repl_env.run_script(io::stdin().lock(), args.echo)?
+            }
+            None => repl_env._loop()?,
+        }
     } else {
-        let data: toml::Value = raw_config
-            .try_into()
-            .expect("The resulting TOML has an value error occurred... That shouldn't happened.");
-
-        let root = match args.root {
-            Some(v) => v,
-            None => template_registry
-                .get_templates()
-                .keys()
-                .min()
-                .expect("There's no templates registered in the registry.")
-                .clone(),
+        let prepared = renderer.prepare().map_err(diagnose)?;
+        warn_skipped(messages, &prepared.skipped);
+        warn_skipped_data(messages, &prepared.skipped_data);
+
+        let output_path = prepared.output_template(args.output.as_deref());
+        if args.check_output && output_path.is_none() {
+            return Err(Failure::new(
+                ExitStatus::Usage,
+                "The --check-output option requires an output path.",
+            )
+            .into());
+        }
+
+        if args.each.is_some() && output_path.is_none() {
+            return Err(Failure::new(
+                ExitStatus::Usage,
+                "The --each option requires an output path.",
+            )
+            .into());
+        }
+
+        let targets = prepared.targets(
+            args.each.as_deref(),
+            output_path,
+            args.output_root.as_deref(),
+        )?;
+        let write_options = WriteOptions {
+            mode: args.mode,
+            diff: args.diff,
+            check: args.check_output,
         };
-        let rendered_template = template_registry.render(&root, &data)?;
 
-        match args.output {
-            Some(output_path) => {
-                let mut output_file = File::create(output_path)?;
+        let mut outdated_paths = vec![];
+        for target in targets.iter() {
+            let started = Instant::now();
+            let rendered = prepared
+                .render_target(target, &write_options)
+                .map_err(|e| match e {
+                    hantemcli::Error::Render(e) => Box::new(Diagnostics {
+                        status: ExitStatus::Render,
+                        diagnostics: vec![prepared.diagnose(&e)],
+                    }),
+                    e => diagnose(e),
+                })?;
+
+            match &target.path {
+                Some(output_path) => {
+                    if let Some(diff) = &rendered.diff {
+                        print!("{}", diff);
+                        outdated_paths.push(output_path.clone());
+                    }
 
-                output_file.write(rendered_template.as_bytes())?;
+                    messages.output(
+                        Some(output_path),
+                        &rendered.content,
+                        rendered.written,
+                        started.elapsed(),
+                    );
+                }
+                None => {
+                    print!("{}", rendered.content);
+                    messages.output(None, &rendered.content, true, started.elapsed());
+                }
             }
-            None => println!("{}", rendered_template),
+        }
+
+        if args.check_output && !outdated_paths.is_empty() {
+            return Err(Failure::new(
+                ExitStatus::Failure,
+                format!(
+                    "{} output file(s) are out of date: {:?}",
+                    outdated_paths.len(),
+                    outdated_paths
+                ),
+            )
+            .into());
         }
     }
 
     Ok(())
 }
 
+/// Print the partial dependency graph of the given templates.
+/// Missing partials are reported in the standard error and results in an error.
+/// Cycles between the partials are only reported as warnings since recursive partials are valid.
+pub fn print_graph(
+    sources: args::Sources,
+    root: Option<String>,
+    format: GraphFormat,
+    messages: &mut Messages,
+) -> Result<(), Box<dyn Error>> {
+    let (template_registry, registration) = sources.renderer().register_templates()?;
+    warn_skipped(messages, &registration.failures);
+
+    let mut partial_graph = graph::PartialGraph::from_registry(&template_registry);
+    if let Some(root) = root {
+        if !template_registry.has_template(&root) {
+            return Err(hantemcli::Error::TemplateNotFound(root).into());
+        }
+
+        partial_graph = partial_graph.reachable_from(&root);
+    }
+
+    match format {
+        GraphFormat::Dot => print!("{}", partial_graph.to_dot()),
+        GraphFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&partial_graph.to_json())?
+        ),
+    }
+
+    let missing = partial_graph.missing();
+    for (name, reference) in missing.iter() {
+        let message = match reference.position {
+            Some((line, column)) => format!(
+                "Template {:?} references a missing partial {:?} at line {}, column {}.",
+                name, reference.name, line, column
+            ),
+            None => format!(
+                "Template {:?} references a missing partial {:?}.",
+                name, reference.name
+            ),
+        };
+        let diagnostic = Diagnostic {
+            code: "missing-partial",
+            message: format!("The partial {:?} is missing.", reference.name),
+            template: Some(name.to_string()),
+            path: registration
+                .templates
+                .iter()
+                .find(|t| t.name == *name)
+                .map(|t| t.path.to_path_buf()),
+            line: reference.position.map(|(line, _)| line),
+            column: reference.position.map(|(_, column)| column),
+            ..Default::default()
+        };
+        messages.report(message, diagnostic);
+    }
+
+    for cycle in partial_graph.cycles().iter() {
+        let diagnostic = Diagnostic {
+            severity: Severity::Warning,
+            code: "partial-cycle",
+            message: format!(
+                "There's a cycle between the partials: {}",
+                cycle.join(" -> ")
+            ),
+            template: cycle.first().map(|v| v.to_string()),
+            ..Default::default()
+        };
+        messages.report(format!("warning: {}", diagnostic.message), diagnostic);
+    }
+
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(Failure::new(
+            ExitStatus::Template,
+            format!(
+                "The partial graph has {} missing partial(s).",
+                missing.len()
+            ),
+        )
+        .into()),
+    }
+}
+
+/// Print the variables referenced by the templates compared with the keys provided by the data.
+/// Environment variables are not listed as unused keys.
+pub fn print_report(
+    sources: args::Sources,
+    root: Option<String>,
+    messages: &mut Messages,
+) -> Result<(), Box<dyn Error>> {
+    let renderer = sources.renderer();
+    let (template_registry, registration) = renderer.register_templates()?;
+    warn_skipped(messages, &registration.failures);
+    let registered_templates = registration.templates;
+
+    let roots: Vec<String> = match root {
+        Some(v) => {
+            if !template_registry.has_template(&v) {
+                return Err(hantemcli::Error::TemplateNotFound(v).into());
+            }
+
+            vec![v]
+        }
+        None => graph::PartialGraph::from_registry(&template_registry)
+            .roots()
+            .into_iter()
+            .filter(|name| {
+                !registered_templates
+                    .iter()
+                    .any(|t| t.name == *name && t.front_matter.partial)
+            })
+            .map(|v| v.to_string())
+            .collect(),
+    };
+    let roots: Vec<&str> = roots.iter().map(|v| v.as_str()).collect();
+
+    // The environment variables are the last data layers and not counted as the provided keys so
+    // the data before them is kept while merging.
+    let data_layers = renderer.data_layers();
+    let (provided_layers, environment_layers) = data_layers.split_at(
+        data_layers
+            .iter()
+            .position(|layer| matches!(layer, DataLayer::Environment { .. }))
+            .unwrap_or(data_layers.len()),
+    );
+    let (mut data, mut skipped_data) = data::merge_layers(provided_layers, false)?;
+    let provided_data: toml::Value = data.clone().try_into()?;
+    skipped_data.extend(data::merge_layers_into(
+        &mut data,
+        environment_layers,
+        false,
+    )?);
+    warn_skipped_data(messages, &skipped_data);
+    let mut data: toml::Value = data.try_into()?;
+
+    // The default data from the front matter of the roots are not reported as missing.
+    for registered_template in registered_templates.iter() {
+        if let Some(defaults) = &registered_template.front_matter.data {
+            if roots.contains(&registered_template.name.as_str()) {
+                data::merge_defaults(&mut data, &toml::Value::try_from(defaults)?);
+            }
+        }
+    }
+
+    let report = report::Report::new(&template_registry, &roots, &data, &provided_data);
+    print!("{}", report);
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn basic_usage_test() {
-        let args = vec![
+        let args = [
             "hantemcli",
             "tests/template.hbs",
             "--",
@@ -101,7 +442,90 @@ mod tests {
         ];
         let parsed_args = Hantemcli::from_iter(args.iter());
 
-        let result = parse_args(parsed_args);
+        let result = parse_args(parsed_args, &mut Messages::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn check_usage_test() {
+        let args = [
+            "hantemcli",
+            "check",
+            "--root",
+            "template",
+            "tests/template.hbs",
+            "--",
+            "tests/default.toml",
+        ];
+        let parsed_args = Hantemcli::from_iter(args.iter());
+
+        let result = parse_args(parsed_args, &mut Messages::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn each_duplicate_output_test() {
+        let directory = std::env::temp_dir().join(format!("hantemcli-each-{}", std::process::id()));
+        let output = directory.join("{{@root.dogs}}.txt");
+        let args = [
+            "hantemcli",
+            "--each",
+            "sparkpost",
+            "--output",
+            output.to_str().unwrap(),
+            "tests/template.hbs",
+            "--",
+            "tests/default.toml",
+        ];
+
+        // None of the items are written if an output path is rendered for multiple items.
+        let error =
+            parse_args(Hantemcli::from_iter(args.iter()), &mut Messages::default()).unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Output);
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn exit_status_test() {
+        let exit_status = |args: &[&str]| {
+            let error = parse_args(Hantemcli::from_iter(args.iter()), &mut Messages::default())
+                .unwrap_err();
+            ExitStatus::from_error(error.as_ref())
+        };
+
+        assert_eq!(
+            exit_status(&["hantemcli", "--root", "ghost", "tests/template.hbs"]),
+            ExitStatus::Template
+        );
+        assert_eq!(
+            exit_status(&[
+                "hantemcli",
+                "--each",
+                "ghost",
+                "--output",
+                "{{@key}}.txt",
+                "tests/template.hbs",
+                "--",
+                "tests/default.toml"
+            ]),
+            ExitStatus::Data
+        );
+        assert_eq!(
+            exit_status(&["hantemcli", "--strict", "tests/template.hbs"]),
+            ExitStatus::Render
+        );
+    }
+
+    #[test]
+    fn graph_usage_test() {
+        let args = ["hantemcli", "graph", "--root", "template", "tests/"];
+        let parsed_args = Hantemcli::from_iter(args.iter());
+
+        let result = parse_args(parsed_args, &mut Messages::default());
         assert!(result.is_ok());
+
+        // The recursive partial in `tests/base.hbs` is not an error.
+        let parsed_args = Hantemcli::from_iter(["hantemcli", "graph", "tests/"].iter());
+        assert!(parse_args(parsed_args, &mut Messages::default()).is_ok());
     }
 }
@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::exit::ExitStatus;
+use crate::Diagnostics;
+use hantemcli::diagnostic::{Diagnostic, Severity};
+
+/// The format of the warnings and errors printed in the standard error.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MessageFormat {
+    #[default]
+    Human,
+
+    /// Each message is printed as a line of JSON with a summary at the end.
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("No such message format as {:?}", string)),
+        }
+    }
+}
+
+/// A rendered output of the root template.
+#[derive(Debug)]
+struct Output {
+    /// The path of the output file or `None` for the standard output.
+    path: Option<PathBuf>,
+    bytes: usize,
+
+    /// Whether the content has been written (i.e., not skipped for being unchanged).
+    written: bool,
+    duration: Duration,
+}
+
+/// The printer of the warnings and errors of the program which also keeps track of the outputs
+/// for the summary.
+#[derive(Debug)]
+pub struct Messages {
+    format: MessageFormat,
+    started: Instant,
+    errors: usize,
+    warnings: usize,
+    outputs: Vec<Output>,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::new(MessageFormat::default())
+    }
+}
+
+impl Messages {
+    pub fn new(format: MessageFormat) -> Self {
+        Self {
+            format,
+            started: Instant::now(),
+            errors: 0,
+            warnings: 0,
+            outputs: vec![],
+        }
+    }
+
+    /// Print the message as is in the human format or the diagnostic in the JSON format.
+    pub fn report<D: Display>(
+        &mut self,
+        message: D,
+        diagnostic: Diagnostic,
+    ) {
+        match diagnostic.severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+        }
+
+        match self.format {
+            MessageFormat::Human => eprintln!("{}", message),
+            MessageFormat::Json => eprintln!("{}", diagnostic_record(&diagnostic)),
+        }
+    }
+
+    pub fn diagnostic(
+        &mut self,
+        diagnostic: Diagnostic,
+    ) {
+        self.report(diagnostic.clone(), diagnostic);
+    }
+
+    /// Record the rendered output for the summary.
+    pub fn output(
+        &mut self,
+        path: Option<&Path>,
+        content: &str,
+        written: bool,
+        duration: Duration,
+    ) {
+        self.outputs.push(Output {
+            path: path.map(|v| v.to_path_buf()),
+            bytes: content.len(),
+            written,
+            duration,
+        });
+    }
+
+    /// Print the error that stopped the program then the summary in the JSON format.
+    pub fn finish(
+        mut self,
+        result: &Result<(), Box<dyn Error>>,
+    ) {
+        if let Err(e) = result {
+            match self.format {
+                MessageFormat::Human => match e.downcast_ref::<Diagnostics>() {
+                    Some(diagnostics) => eprintln!("{}", diagnostics),
+                    None => eprintln!("Final error:\n---\n{}\n---\n", e),
+                },
+                MessageFormat::Json => {
+                    for diagnostic in error_diagnostics(e.as_ref()) {
+                        self.diagnostic(diagnostic);
+                    }
+                }
+            }
+        }
+
+        if self.format == MessageFormat::Json {
+            eprintln!("{}", self.summary_record(result));
+        }
+    }
+
+    fn summary_record(
+        &self,
+        result: &Result<(), Box<dyn Error>>,
+    ) -> serde_json::Value {
+        let outputs: Vec<serde_json::Value> = self
+            .outputs
+            .iter()
+            .map(|output| {
+                json!({
+                    "path": output.path,
+                    "bytes": output.bytes,
+                    "written": output.written,
+                    "duration_ms": duration_ms(output.duration),
+                })
+            })
+            .collect();
+
+        json!({
+            "type": "summary",
+            "success": result.is_ok(),
+            "exit_code": match result {
+                Ok(_) => 0,
+                Err(e) => ExitStatus::from_error(e.as_ref()).code(),
+            },
+            "errors": self.errors,
+            "warnings": self.warnings,
+            "outputs": outputs,
+            "duration_ms": duration_ms(self.started.elapsed()),
+        })
+    }
+}
+
+fn diagnostic_record(diagnostic: &Diagnostic) -> serde_json::Value {
+    json!({
+        "type": "diagnostic",
+        "severity": diagnostic.severity.to_string(),
+        "code": diagnostic.code,
+        "message": diagnostic.message,
+        "file": diagnostic.path,
+        "line": diagnostic.line,
+        "column": diagnostic.column,
+        "template": diagnostic.template,
+        "notes": diagnostic.notes,
+        "help": diagnostic.help,
+    })
+}
+
+/// Get the diagnostics of the error that stopped the program.
+fn error_diagnostics(error: &(dyn Error + 'static)) -> Vec<Diagnostic> {
+    if let Some(diagnostics) = error.downcast_ref::<Diagnostics>() {
+        return diagnostics.diagnostics.clone();
+    }
+
+    if let Some(error) = error.downcast_ref::<hantemcli::Error>() {
+        return Diagnostic::from_error(error);
+    }
+
+    vec![Diagnostic {
+        code: ExitStatus::from_error(error).name(),
+        message: error.to_string(),
+        ..Default::default()
+    }]
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
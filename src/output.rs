@@ -0,0 +1,492 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::render;
+use crate::templates;
+use crate::Error;
+
+/// The formatting of the rendered output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputFormat {
+    pub newline: Newline,
+
+    /// The line endings of the output which are kept as rendered if unset.
+    pub line_ending: Option<LineEnding>,
+
+    /// Whether the output starts with the UTF-8 byte order mark.
+    pub bom: bool,
+
+    /// Whether the whitespace at the end of the output is removed.
+    pub trim: bool,
+}
+
+/// What to do with the trailing newline of the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Newline {
+    #[default]
+    Keep,
+    Add,
+    Strip,
+}
+
+impl FromStr for Newline {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "keep" => Ok(Self::Keep),
+            "add" => Ok(Self::Add),
+            "strip" => Ok(Self::Strip),
+            _ => Err(format!("No such newline mode as {:?}", string)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl FromStr for LineEnding {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::Crlf),
+            _ => Err(format!("No such line ending as {:?}", string)),
+        }
+    }
+}
+
+/// How the rendered output is compared with and written into its output file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WriteOptions {
+    /// The permissions of the written file (see `write_file`).
+    pub mode: Option<u32>,
+
+    /// Whether the difference from the existing content of the file is given (see `diff_file`).
+    pub diff: bool,
+
+    /// Whether the output is only compared with the existing file without writing it.
+    pub check: bool,
+}
+
+/// Render the output path as a template with the data.
+///
+/// The rendered values are not escaped and the context (from the base path) and the local
+/// variables (e.g., `@meta`) are the same as the root template.
+/// If `output_root` is given, the rendered path must be inside of it.
+pub fn render_path<T: Serialize>(
+    path: &Path,
+    data: &T,
+    base_path: &[String],
+    locals: &BTreeMap<String, serde_json::Value>,
+    strict: bool,
+    output_root: Option<&Path>,
+) -> Result<PathBuf, Error> {
+    let mut registry = handlebars::Handlebars::new();
+    registry.set_strict_mode(strict);
+    registry.register_escape_fn(handlebars::no_escape);
+    registry
+        .register_template_string("output", path.to_string_lossy())
+        .map_err(|e| {
+            Error::OutputPath(format!(
+                "The output path {:?} is not a valid template: {}",
+                path, e
+            ))
+        })?;
+    let rewritten = render::rewrite_local_paths(&registry, &locals.keys().collect::<Vec<_>>());
+
+    let rendered_path = PathBuf::from(render::render_item(
+        &registry, &rewritten, "output", data, base_path, locals,
+    )?);
+    if rendered_path.as_os_str().is_empty() {
+        return Err(Error::OutputPath(format!(
+            "The output path {:?} renders into an empty path.",
+            path
+        )));
+    }
+
+    if let Some(output_root) = output_root {
+        let current_dir = env::current_dir()?;
+        let absolute_path = templates::naively_normalize_path(current_dir.join(&rendered_path));
+        if !absolute_path.starts_with(templates::naively_normalize_path(
+            current_dir.join(output_root),
+        )) {
+            return Err(Error::OutputPath(format!(
+                "The output path {:?} is outside of the output root {:?}.",
+                rendered_path, output_root
+            )));
+        }
+    }
+
+    Ok(rendered_path)
+}
+
+/// Format the rendered output.
+/// The whitespace is trimmed first followed by the trailing newline, the line endings, then the
+/// byte order mark.
+pub fn format(
+    content: &str,
+    output_format: &OutputFormat,
+) -> String {
+    let mut content = match output_format.trim {
+        true => content.trim_end().to_string(),
+        false => content.to_string(),
+    };
+
+    match output_format.newline {
+        Newline::Keep => (),
+        Newline::Add => {
+            if !content.ends_with('\n') {
+                content.push('\n');
+            }
+        }
+        Newline::Strip => {
+            if content.ends_with('\n') {
+                content.pop();
+                if content.ends_with('\r') {
+                    content.pop();
+                }
+            }
+        }
+    }
+
+    content = match output_format.line_ending {
+        Some(LineEnding::Lf) => content.replace("\r\n", "\n"),
+        Some(LineEnding::Crlf) => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        None => content,
+    };
+
+    match output_format.bom {
+        true if !content.starts_with('\u{feff}') => format!("\u{feff}{}", content),
+        _ => content,
+    }
+}
+
+/// Write the content into the file creating the missing parent directories.
+///
+/// The content is written into a temporary file in the same directory then renamed into place so
+/// the file is never left half-written.
+/// The file is not touched if it already has the same content.
+/// If `mode` is given, it is set as the permissions of the file (only on Unix).
+/// Otherwise, the permissions of the replaced file are kept.
+///
+/// Returns whether the content of the file has been written.
+pub fn write_file(
+    path: &Path,
+    content: &str,
+    mode: Option<u32>,
+) -> Result<bool, Error> {
+    write_file_atomically(path, content, mode).map_err(|source| Error::Output {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn write_file_atomically(
+    path: &Path,
+    content: &str,
+    mode: Option<u32>,
+) -> io::Result<bool> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => {
+            fs::create_dir_all(parent)?;
+            parent
+        }
+        _ => Path::new("."),
+    };
+
+    let existing_metadata = fs::metadata(path).ok();
+    if existing_metadata.as_ref().is_some_and(|v| v.is_file())
+        && fs::read(path)? == content.as_bytes()
+    {
+        if let Some(mode) = mode {
+            set_mode(path, mode)?;
+        }
+
+        return Ok(false);
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "The path has no file name."))?;
+    let temp_path = parent.join(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+
+    let result = write_temp_file(&temp_path, content, mode, existing_metadata.as_ref())
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        fs::remove_file(&temp_path).ok();
+        return Err(e);
+    }
+
+    Ok(true)
+}
+
+fn write_temp_file(
+    temp_path: &Path,
+    content: &str,
+    mode: Option<u32>,
+    existing_metadata: Option<&fs::Metadata>,
+) -> io::Result<()> {
+    let mut temp_file = new_file_options(mode, existing_metadata)
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.sync_all()?;
+
+    match (mode, existing_metadata) {
+        (Some(mode), _) => set_mode(temp_path, mode),
+        (None, Some(metadata)) => fs::set_permissions(temp_path, metadata.permissions()),
+        (None, None) => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(
+    path: &Path,
+    mode: u32,
+) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(
+    _path: &Path,
+    _mode: u32,
+) -> io::Result<()> {
+    Ok(())
+}
+
+/// Get the options for opening the file with the permissions set on its creation so the content
+/// is never readable with wider permissions than the final ones.
+/// It is the given mode, the mode of the existing file, or the default of `File::create`.
+#[cfg(unix)]
+fn new_file_options(
+    mode: Option<u32>,
+    existing_metadata: Option<&fs::Metadata>,
+) -> fs::OpenOptions {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mode = mode
+        .or_else(|| existing_metadata.map(|v| v.permissions().mode() & 0o7777))
+        .unwrap_or(0o666);
+    let mut options = fs::OpenOptions::new();
+    options.mode(mode);
+    options
+}
+
+#[cfg(not(unix))]
+fn new_file_options(
+    _mode: Option<u32>,
+    _existing_metadata: Option<&fs::Metadata>,
+) -> fs::OpenOptions {
+    fs::OpenOptions::new()
+}
+
+/// Append the content to the end of the file creating the missing parent directories and the
+/// file itself.
+/// The byte order mark at the start of the content is skipped if the file is not empty.
+/// If `mode` is given, it is set as the permissions of the file (only on Unix).
+///
+/// Returns the number of bytes appended.
+pub fn append_file(
+    path: &Path,
+    content: &str,
+    mode: Option<u32>,
+) -> Result<usize, Error> {
+    append_to_file(path, content, mode).map_err(|source| Error::Output {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn append_to_file(
+    path: &Path,
+    content: &str,
+    mode: Option<u32>,
+) -> io::Result<usize> {
+    if let Some(parent) = path.parent().filter(|v| !v.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = new_file_options(mode, None)
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    // The permissions of an existing file are changed before the content is added.
+    if let Some(mode) = mode {
+        set_mode(path, mode)?;
+    }
+
+    let content = match file.metadata()?.len() {
+        0 => content,
+        _ => content.strip_prefix('\u{feff}').unwrap_or(content),
+    };
+    file.write_all(content.as_bytes())?;
+
+    Ok(content.len())
+}
+
+/// Parse the file permissions in octal notation (e.g., `0600`, `644`).
+pub fn parse_mode(mode: &str) -> Result<u32, String> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(v) if v <= 0o7777 => Ok(v),
+        _ => Err(format!(
+            "{:?} is not a valid file mode in octal notation.",
+            mode
+        )),
+    }
+}
+
+/// Get the unified diff between the existing content of the file and the new content.
+/// A missing file is compared as an empty file.
+/// Returns `None` if the contents are the same.
+pub fn diff_file(
+    path: &Path,
+    content: &str,
+) -> Result<Option<String>, Error> {
+    let existing_content = match fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(source) => {
+            return Err(Error::Output {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+
+    if existing_content == content {
+        return Ok(None);
+    }
+
+    let path = path.to_string_lossy();
+    let diff = similar::TextDiff::from_lines(existing_content.as_str(), content)
+        .unified_diff()
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string();
+
+    Ok(Some(diff))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn render_path_test() {
+        let data = json!({ "env": "prod", "service": { "name": "api" }, "up": "../.." });
+        let locals = BTreeMap::new();
+        let root = Path::new("build");
+
+        assert_eq!(
+            render_path(
+                Path::new("build/{{env}}/{{service.name}}.conf"),
+                &data,
+                &[],
+                &locals,
+                true,
+                Some(root)
+            )
+            .unwrap(),
+            PathBuf::from("build/prod/api.conf")
+        );
+        assert!(render_path(
+            Path::new("build/{{up}}/{{service.name}}.conf"),
+            &data,
+            &[],
+            &locals,
+            true,
+            Some(root)
+        )
+        .is_err());
+        assert!(render_path(Path::new("{{ghost}}.conf"), &data, &[], &locals, true, None).is_err());
+    }
+
+    #[test]
+    fn write_file_test() {
+        let directory = env::temp_dir().join(format!("hantemcli-output-{}", process::id()));
+        let path = directory.join("nested/output.conf");
+
+        assert!(write_file(&path, "content", Some(0o600)).unwrap());
+        assert!(!write_file(&path, "content", None).unwrap());
+        assert!(write_file(&path, "new content", None).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = fs::metadata(&path).unwrap().permissions();
+            assert_eq!(permissions.mode() & 0o7777, 0o600);
+
+            // The permissions are set before anything is written.
+            let secret_path = directory.join("secret.conf");
+            new_file_options(Some(0o600), None)
+                .write(true)
+                .create_new(true)
+                .open(&secret_path)
+                .unwrap();
+            let permissions = fs::metadata(&secret_path).unwrap().permissions();
+            assert_eq!(permissions.mode() & 0o7777, 0o600);
+            fs::remove_file(&secret_path).unwrap();
+        }
+
+        append_file(&path, "\nappended", None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new content\nappended");
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(parse_mode("0600"), Ok(0o600));
+        assert!(parse_mode("0800").is_err());
+    }
+
+    #[test]
+    fn diff_file_test() {
+        let path = env::temp_dir().join(format!("hantemcli-diff-{}.conf", process::id()));
+        fs::write(&path, "a = 1\nb = 2\n").unwrap();
+
+        let diff = diff_file(&path, "a = 1\nb = 3\n").unwrap().unwrap();
+        assert!(diff.contains("-b = 2\n+b = 3\n"));
+        assert_eq!(diff_file(&path, "a = 1\nb = 2\n").unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+        assert!(diff_file(&path, "a = 1\n").unwrap().is_some());
+    }
+
+    #[test]
+    fn format_test() {
+        let output_format = OutputFormat {
+            newline: Newline::Add,
+            line_ending: Some(LineEnding::Crlf),
+            bom: true,
+            trim: true,
+        };
+        assert_eq!(format("a\r\nb\n \n", &output_format), "\u{feff}a\r\nb\r\n");
+
+        let output_format = OutputFormat {
+            newline: Newline::Strip,
+            ..Default::default()
+        };
+        assert_eq!(format("a\nb\r\n", &output_format), "a\nb");
+        assert_eq!(format("a\n\n", &OutputFormat::default()), "a\n\n");
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+use handlebars::template::{Parameter, Template, TemplateElement};
+use handlebars::{
+    BlockContext, BlockParams, Context, Output, Path, RenderContext, RenderError, Renderable,
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::data;
+use crate::variables;
+
+/// An output collecting the rendered template into a string.
+#[derive(Debug, Default)]
+struct StringOutput {
+    buffer: String,
+}
+
+impl Output for StringOutput {
+    fn write(
+        &mut self,
+        segment: &str,
+    ) -> Result<(), io::Error> {
+        self.buffer.push_str(segment);
+        Ok(())
+    }
+}
+
+/// An element of the data rendered as the context of the root template.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Item {
+    /// The path of the element from the root of the data.
+    pub base_path: Vec<String>,
+
+    /// The local variables of the element similar to the `each` helper (e.g., `@index`, `@key`).
+    pub locals: BTreeMap<String, serde_json::Value>,
+}
+
+/// Get the elements of the array or table at the path in the data.
+/// Similar to the `each` helper, the elements have the `@index`, `@key`, `@first`, and `@last`
+/// local variables with the index as the key for the arrays.
+pub fn items<S: AsRef<str>>(
+    data: &toml::Value,
+    path: &[S],
+) -> Result<Vec<Item>, String> {
+    let keys: Vec<String> = match data::lookup(data, path) {
+        Some(toml::Value::Array(array)) => (0..array.len()).map(|i| i.to_string()).collect(),
+        Some(toml::Value::Table(table)) => table.keys().cloned().collect(),
+        Some(_v) => {
+            return Err(format!(
+                "The value of `{}` is not an array or a table.",
+                variables::dotted_path(path)
+            ))
+        }
+        None => {
+            return Err(format!(
+                "The data has no value at `{}`.",
+                variables::dotted_path(path)
+            ))
+        }
+    };
+
+    let length = keys.len();
+    Ok(keys
+        .into_iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let mut base_path: Vec<String> = path.iter().map(|v| v.as_ref().to_string()).collect();
+            let mut locals = BTreeMap::new();
+            locals.insert("index".to_string(), json!(index));
+            locals.insert("key".to_string(), json!(key));
+            locals.insert("first".to_string(), json!(index == 0));
+            locals.insert("last".to_string(), json!(index + 1 == length));
+            base_path.push(key);
+
+            Item { base_path, locals }
+        })
+        .collect())
+}
+
+/// The local variables of the items (i.e., `@index`, `@key`, `@first`, and `@last`).
+pub static ITEM_LOCALS: &[&str] = &["index", "key", "first", "last"];
+
+/// Render the registered template with the value at the base path of the data as the context.
+/// The whole data is still available as `@root`.
+///
+/// The local variables (e.g., `@meta`) are given without the `@` prefix and set in the root block
+/// of the template.
+/// Inside of other blocks, they are accessible from the parent block (e.g., `@../meta`).
+/// The values inside of them can be referred with a path (e.g., `{{@meta.title}}`) in the
+/// templates returned from `rewrite_local_paths`, which are used in place of the registered ones.
+pub fn render_item<T: Serialize>(
+    registry: &handlebars::Handlebars,
+    rewritten: &BTreeMap<String, Template>,
+    name: &str,
+    data: &T,
+    base_path: &[String],
+    locals: &BTreeMap<String, serde_json::Value>,
+) -> Result<String, RenderError> {
+    let template = rewritten
+        .get(name)
+        .or_else(|| registry.get_template(name))
+        .ok_or_else(|| RenderError::new(format!("Template not found: {}", name)))?;
+
+    let context = Context::wraps(data)?;
+    let mut render_context = RenderContext::new(template.name.as_ref());
+    for (name, template) in rewritten {
+        render_context.set_partial(name.to_string(), template);
+    }
+
+    let mut block = BlockContext::new();
+    block.base_path_mut().extend(base_path.iter().cloned());
+    let mut block_params = BlockParams::new();
+    let local_names: Vec<String> = locals.keys().map(|key| format!("@{}", key)).collect();
+    for (local_name, value) in local_names.iter().zip(locals.values()) {
+        block.set_local_var(local_name.to_string(), value.clone());
+        block_params.add_value(local_name, value.clone())?;
+    }
+    block.set_block_params(block_params);
+    render_context.push_block(block);
+
+    let mut output = StringOutput::default();
+    template.render(registry, &context, &mut render_context, &mut output)?;
+
+    Ok(output.buffer)
+}
+
+/// Rewrite the paths inside of the local variables with the names (e.g., `@meta.title`,
+/// `@../meta.title`) in the registered templates to be resolved by `render_item`.
+///
+/// Handlebars only resolves the local variable itself from such a path so they are rewritten into
+/// the paths of the block parameters named after them (i.e., `[@meta].title`).
+/// Only the templates with such paths are returned.
+pub fn rewrite_local_paths<S: AsRef<str>>(
+    registry: &handlebars::Handlebars,
+    names: &[S],
+) -> BTreeMap<String, Template> {
+    let names: BTreeSet<&str> = names.iter().map(|v| v.as_ref()).collect();
+
+    registry
+        .get_templates()
+        .iter()
+        .filter_map(|(name, template)| {
+            let mut template = template.clone();
+            match rewrite_template(&mut template, &names) {
+                true => Some((name.to_string(), template)),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+/// Returns whether the template has been changed.
+fn rewrite_template(
+    template: &mut Template,
+    names: &BTreeSet<&str>,
+) -> bool {
+    let mut changed = false;
+    for element in template.elements.iter_mut() {
+        changed |= rewrite_element(element, names);
+    }
+
+    changed
+}
+
+fn rewrite_element(
+    element: &mut TemplateElement,
+    names: &BTreeSet<&str>,
+) -> bool {
+    let mut changed = false;
+    match element {
+        TemplateElement::HTMLExpression(parameter) => {
+            changed |= rewrite_parameter(parameter, names)
+        }
+        TemplateElement::Expression(helper) | TemplateElement::HelperBlock(helper) => {
+            for parameter in std::iter::once(&mut helper.name)
+                .chain(helper.params.iter_mut())
+                .chain(helper.hash.values_mut())
+            {
+                changed |= rewrite_parameter(parameter, names);
+            }
+
+            for t in helper.template.iter_mut().chain(helper.inverse.iter_mut()) {
+                changed |= rewrite_template(t, names);
+            }
+        }
+        TemplateElement::DecoratorExpression(decorator)
+        | TemplateElement::DecoratorBlock(decorator)
+        | TemplateElement::PartialExpression(decorator)
+        | TemplateElement::PartialBlock(decorator) => {
+            for parameter in decorator
+                .params
+                .iter_mut()
+                .chain(decorator.hash.values_mut())
+            {
+                changed |= rewrite_parameter(parameter, names);
+            }
+
+            if let Some(t) = &mut decorator.template {
+                changed |= rewrite_template(t, names);
+            }
+        }
+        _ => (),
+    }
+
+    changed
+}
+
+fn rewrite_parameter(
+    parameter: &mut Parameter,
+    names: &BTreeSet<&str>,
+) -> bool {
+    match parameter {
+        Parameter::Path(Path::Local((_, name, raw))) if names.contains(name.as_str()) => {
+            let rest = match raw.split_once(name.as_str()) {
+                Some((_, rest)) if !rest.is_empty() => rest,
+                _ => return false,
+            };
+
+            match Path::parse(&format!("[@{}]{}", name, rest)) {
+                Ok(Path::Relative((segments, _))) => {
+                    *parameter = Parameter::Path(Path::Relative((segments, raw.to_string())));
+                    true
+                }
+                _ => false,
+            }
+        }
+        Parameter::Subexpression(subexpression) => {
+            rewrite_element(&mut subexpression.element, names)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_with_locals_test() {
+        let mut registry = handlebars::Handlebars::new();
+        registry
+            .register_template_string(
+                "root",
+                "{{lookup @meta \"title\"}} {{name}}{{#each items}} {{lookup @../meta \"title\"}}{{/each}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string(
+                "paths",
+                "{{@meta.title}}{{#each items}} {{@../meta.title}}{{/each}} {{> footer}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string(
+                "footer",
+                "{{#if @meta.author.name}}{{@meta.author.name}}{{/if}}",
+            )
+            .unwrap();
+
+        let mut locals = BTreeMap::new();
+        locals.insert(
+            "meta".to_string(),
+            json!({ "title": "Hello", "author": { "name": "Ann" } }),
+        );
+
+        let data = json!({ "name": "world", "items": [1], "meta": { "title": "data" } });
+        let rewritten = rewrite_local_paths(&registry, &["meta"]);
+        let rendered = render_item(&registry, &rewritten, "root", &data, &[], &locals).unwrap();
+        assert_eq!(rendered, "Hello world Hello");
+
+        let rendered = render_item(&registry, &rewritten, "paths", &data, &[], &locals).unwrap();
+        assert_eq!(rendered, "Hello Hello Ann");
+    }
+
+    #[test]
+    fn render_items_test() {
+        let mut registry = handlebars::Handlebars::new();
+        registry
+            .register_template_string("root", "{{@index}} {{@key}} {{name}} {{@root.site}}")
+            .unwrap();
+
+        let data: toml::Value = toml::from_str(
+            r#"
+            site = "example.com"
+            users = [{ name = "a" }, { name = "b" }]
+            [hosts.alpha]
+            name = "c"
+            "#,
+        )
+        .unwrap();
+
+        let rendered: Vec<String> = items(&data, &["users"])
+            .unwrap()
+            .iter()
+            .chain(items(&data, &["hosts"]).unwrap().iter())
+            .map(|item| {
+                render_item(
+                    &registry,
+                    &BTreeMap::new(),
+                    "root",
+                    &data,
+                    &item.base_path,
+                    &item.locals,
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "0 0 a example.com",
+                "1 1 b example.com",
+                "0 alpha c example.com"
+            ]
+        );
+        assert!(items(&data, &["site"]).is_err());
+    }
+}
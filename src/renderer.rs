@@ -0,0 +1,639 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::data::{self, DataLayer};
+use crate::diagnostic::Diagnostic;
+use crate::output::{self, OutputFormat, WriteOptions};
+use crate::render::{self, Item};
+use crate::schema;
+use crate::templates::{
+    self, EscapeMode, FrontMatter, NameStyle, RegisteredTemplate, Registration,
+    RegistrationFailure, TemplateFilter,
+};
+use crate::Error;
+
+/// The builder for rendering the templates with the layered data.
+///
+/// The templates are searched from the template paths the same way as the command line.
+/// The data layers are merged in the order they are added.
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    template_paths: Vec<PathBuf>,
+    extensions: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    hidden: bool,
+    name_style: NameStyle,
+    keep_going: bool,
+    data_layers: Vec<DataLayer>,
+    require_data_files: bool,
+    root: Option<String>,
+    strict: bool,
+    schema: Option<PathBuf>,
+    schema_defaults: bool,
+    output_format: OutputFormat,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a template file or a directory of the templates.
+    /// A namespace can be given with the `NAMESPACE=PATH` syntax.
+    pub fn template_path<P: Into<PathBuf>>(
+        mut self,
+        path: P,
+    ) -> Self {
+        self.template_paths.push(path.into());
+        self
+    }
+
+    /// Add a file extension of the templates to be searched.
+    /// If there's none, the templates with the `hbs` file extension are searched.
+    pub fn extension<S: Into<String>>(
+        mut self,
+        extension: S,
+    ) -> Self {
+        self.extensions.push(extension.into());
+        self
+    }
+
+    /// Only search the files in the template directories matching one of the glob patterns.
+    pub fn include<S: Into<String>>(
+        mut self,
+        pattern: S,
+    ) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Skip the files in the template directories matching the glob pattern.
+    pub fn exclude<S: Into<String>>(
+        mut self,
+        pattern: S,
+    ) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Search the hidden files and directories in the template directories.
+    pub fn hidden(
+        mut self,
+        hidden: bool,
+    ) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn name_style(
+        mut self,
+        name_style: NameStyle,
+    ) -> Self {
+        self.name_style = name_style;
+        self
+    }
+
+    /// Continue with the successfully registered templates if some of the template files failed
+    /// to be registered.
+    pub fn keep_going(
+        mut self,
+        keep_going: bool,
+    ) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Add a data file as a layer.
+    pub fn data_file<P: Into<PathBuf>>(
+        mut self,
+        path: P,
+    ) -> Self {
+        self.data_layers.push(DataLayer::File(path.into()));
+        self
+    }
+
+    /// Add a value as a data layer.
+    pub fn data_value(
+        mut self,
+        value: toml::Value,
+    ) -> Self {
+        self.data_layers.push(DataLayer::Value(value));
+        self
+    }
+
+    /// Add the environment variables as a data layer.
+    pub fn env(mut self) -> Self {
+        self.data_layers
+            .push(DataLayer::Environment { prefix: None });
+        self
+    }
+
+    /// Add the environment variables starting with the prefix (e.g., `APP_`) as a data layer.
+    /// The prefix is removed from the names.
+    pub fn env_with_prefix<S: Into<String>>(
+        mut self,
+        prefix: S,
+    ) -> Self {
+        self.data_layers.push(DataLayer::Environment {
+            prefix: Some(prefix.into()),
+        });
+        self
+    }
+
+    /// Give an error for a data file that failed to be loaded instead of skipping it.
+    pub fn require_data_files(
+        mut self,
+        require_data_files: bool,
+    ) -> Self {
+        self.require_data_files = require_data_files;
+        self
+    }
+
+    /// Set the name of the template to be rendered.
+    /// By default, it is the alphabetically first template not marked as a partial.
+    pub fn root<S: Into<String>>(
+        mut self,
+        root: S,
+    ) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Give an error for a missing value while rendering.
+    pub fn strict(
+        mut self,
+        strict: bool,
+    ) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Validate the data with the JSON Schema at the path before rendering.
+    pub fn schema<P: Into<PathBuf>>(
+        mut self,
+        path: P,
+    ) -> Self {
+        self.schema = Some(path.into());
+        self
+    }
+
+    /// Fill the missing values in the data with the defaults from the schema.
+    pub fn schema_defaults(
+        mut self,
+        schema_defaults: bool,
+    ) -> Self {
+        self.schema_defaults = schema_defaults;
+        self
+    }
+
+    pub fn output_format(
+        mut self,
+        output_format: OutputFormat,
+    ) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    pub fn template_paths(&self) -> &[PathBuf] {
+        &self.template_paths
+    }
+
+    pub fn data_layers(&self) -> &[DataLayer] {
+        &self.data_layers
+    }
+
+    /// Get the rules for selecting the template files.
+    pub fn template_filter(&self) -> Result<TemplateFilter, Error> {
+        let extensions = match self.extensions.is_empty() {
+            true => vec!["hbs".to_string()],
+            false => self.extensions.clone(),
+        };
+
+        Ok(TemplateFilter::new(
+            &extensions,
+            &self.include,
+            &self.exclude,
+            self.hidden,
+        )?)
+    }
+
+    /// Register the template files into a new template registry.
+    /// With `keep_going`, the failures of the template files skipped are in the registration.
+    pub fn register_templates(
+        &self
+    ) -> Result<(handlebars::Handlebars<'static>, Registration), Error> {
+        let template_filter = self.template_filter()?;
+        let mut template_registry = handlebars::Handlebars::new();
+        template_registry.set_strict_mode(self.strict);
+
+        let registration = templates::register_from_path(
+            &mut template_registry,
+            self.template_paths.clone(),
+            &template_filter,
+            self.name_style,
+        )
+        .into_result(self.keep_going)?;
+
+        Ok((template_registry, registration))
+    }
+
+    /// Merge the data layers without the default data from the front matter.
+    /// Without `require_data_files`, the errors of the data files skipped are returned with the
+    /// merged data.
+    pub fn merge_data(&self) -> Result<(config::Config, Vec<Error>), Error> {
+        data::merge_layers(&self.data_layers, self.require_data_files)
+    }
+
+    /// Register the templates and prepare the data for rendering the root template.
+    ///
+    /// The default data from the front matter of the root template has the lowest precedence.
+    /// The data is then validated with the schema and the required keys from the front matter.
+    pub fn prepare(&self) -> Result<Prepared, Error> {
+        let (mut registry, registration) = self.register_templates()?;
+        let templates = registration.templates;
+
+        // Templates marked as a partial in their front matter are not rendered by default.
+        let root = match &self.root {
+            Some(v) => v.to_string(),
+            None => templates
+                .iter()
+                .filter(|t| !t.front_matter.partial)
+                .map(|t| &t.name)
+                .min()
+                .ok_or(Error::NoTemplates)?
+                .clone(),
+        };
+        if !registry.has_template(&root) {
+            return Err(Error::TemplateNotFound(root));
+        }
+
+        let front_matter = templates
+            .iter()
+            .find(|t| t.name == root)
+            .map(|t| t.front_matter.clone())
+            .unwrap_or_default();
+
+        let (data, skipped_data) = self.merge_data()?;
+        let mut data: toml::Value = data
+            .try_into()
+            .map_err(|source| Error::Data { path: None, source })?;
+
+        if let Some(defaults) = &front_matter.data {
+            let defaults = toml::Value::try_from(defaults).map_err(|e| {
+                Error::InvalidData(format!(
+                    "The default data in the front matter of {:?} cannot be used: {}",
+                    root, e
+                ))
+            })?;
+            data::merge_defaults(&mut data, &defaults);
+        }
+
+        if let Some(schema_path) = &self.schema {
+            data =
+                schema::validate_data(schema_path, data, self.schema_defaults, &self.data_layers)?;
+        }
+
+        let missing_keys: Vec<String> = front_matter
+            .required
+            .iter()
+            .filter(|key| !data::contains_path(&data, &key.split('.').collect::<Vec<&str>>()))
+            .cloned()
+            .collect();
+        if !missing_keys.is_empty() {
+            return Err(Error::MissingKeys {
+                template: root,
+                keys: missing_keys,
+            });
+        }
+
+        if let Some(escape) = front_matter.escape {
+            match escape {
+                EscapeMode::Html => registry.register_escape_fn(handlebars::html_escape),
+                EscapeMode::None => registry.register_escape_fn(handlebars::no_escape),
+            }
+        }
+
+        let mut local_names = vec!["meta"];
+        local_names.extend(render::ITEM_LOCALS);
+        let rewritten = render::rewrite_local_paths(&registry, &local_names);
+
+        Ok(Prepared {
+            registry,
+            rewritten,
+            templates,
+            skipped: registration.failures,
+            skipped_data,
+            data,
+            root,
+            front_matter,
+            strict: self.strict,
+            output_format: self.output_format.clone(),
+        })
+    }
+
+    /// Render the root template with the data into a string.
+    pub fn render_to_string(&self) -> Result<String, Error> {
+        self.prepare()?.render(&Item::default())
+    }
+
+    /// Render the root template with the data into the writer.
+    pub fn render_to_writer<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> Result<(), Error> {
+        writer.write_all(self.render_to_string()?.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// An item to be rendered with its rendered output path, if any.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Target {
+    pub item: Item,
+    pub path: Option<PathBuf>,
+}
+
+/// The rendered output of a target.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rendered {
+    pub content: String,
+
+    /// Whether the content has been written into the output file.
+    pub written: bool,
+
+    /// The difference from the existing output file if it is out of date.
+    pub diff: Option<String>,
+}
+
+/// The registered templates and the data ready to be rendered.
+pub struct Prepared {
+    pub registry: handlebars::Handlebars<'static>,
+    pub templates: Vec<RegisteredTemplate>,
+
+    /// The template files that failed to be registered but skipped with `keep_going`.
+    pub skipped: Vec<RegistrationFailure>,
+
+    /// The errors of the data files that failed to be loaded but skipped without
+    /// `require_data_files`.
+    pub skipped_data: Vec<Error>,
+
+    /// The merged data with the defaults from the front matter.
+    pub data: toml::Value,
+
+    /// The name of the template to be rendered.
+    pub root: String,
+
+    /// The front matter of the root template.
+    pub front_matter: FrontMatter,
+
+    /// The templates with the paths inside of the local variables rewritten to be rendered.
+    rewritten: BTreeMap<String, handlebars::Template>,
+    strict: bool,
+    output_format: OutputFormat,
+}
+
+impl Prepared {
+    /// Get the items of the array or table at the dotted path of the data (e.g., `site.hosts`).
+    pub fn items(
+        &self,
+        path: &str,
+    ) -> Result<Vec<Item>, Error> {
+        render::items(&self.data, &path.split('.').collect::<Vec<&str>>()).map_err(Error::Items)
+    }
+
+    /// Render the root template with the item as the context then format the output.
+    /// The front matter of the root template is available as `@meta`.
+    pub fn render(
+        &self,
+        item: &Item,
+    ) -> Result<String, Error> {
+        let rendered = render::render_item(
+            &self.registry,
+            &self.rewritten,
+            &self.root,
+            &self.data,
+            &item.base_path,
+            &self.locals(item),
+        )?;
+
+        Ok(output::format(&rendered, &self.output_format))
+    }
+
+    /// Render the output path for the item.
+    /// If `output_root` is given, the rendered path must be inside of it.
+    pub fn output_path(
+        &self,
+        path: &Path,
+        item: &Item,
+        output_root: Option<&Path>,
+    ) -> Result<PathBuf, Error> {
+        output::render_path(
+            path,
+            &self.data,
+            &item.base_path,
+            &self.locals(item),
+            self.strict,
+            output_root,
+        )
+    }
+
+    /// Get the output path to be rendered which is the given one or the one from the front matter
+    /// of the root template.
+    pub fn output_template<'a>(
+        &'a self,
+        output_path: Option<&'a Path>,
+    ) -> Option<&'a Path> {
+        output_path.or(self.front_matter.output.as_deref())
+    }
+
+    /// Get the targets of the items at the dotted path of the data (or the whole data if there's
+    /// none) with their output paths rendered from `output_path`.
+    ///
+    /// The output paths are rendered before writing anything so an output path rendered for
+    /// multiple items is an error without leaving the outputs of the earlier items behind.
+    pub fn targets(
+        &self,
+        each: Option<&str>,
+        output_path: Option<&Path>,
+        output_root: Option<&Path>,
+    ) -> Result<Vec<Target>, Error> {
+        let items = match each {
+            Some(each) => self.items(each)?,
+            None => vec![Item::default()],
+        };
+
+        let mut targets: Vec<Target> = vec![];
+        for item in items {
+            let path = match output_path {
+                Some(output_path) => Some(self.output_path(output_path, &item, output_root)?),
+                None => None,
+            };
+
+            // Each item should have its own output file.
+            if let Some(path) = &path {
+                if targets.iter().any(|t| t.path.as_ref() == Some(path)) {
+                    return Err(Error::OutputPath(format!(
+                        "The output path {:?} is rendered for multiple items.",
+                        path
+                    )));
+                }
+            }
+
+            targets.push(Target { item, path });
+        }
+
+        Ok(targets)
+    }
+
+    /// Render the target then compare it with and write it into its output file, if any.
+    pub fn render_target(
+        &self,
+        target: &Target,
+        options: &WriteOptions,
+    ) -> Result<Rendered, Error> {
+        let content = self.render(&target.item)?;
+        let path = match &target.path {
+            Some(v) => v,
+            None => {
+                return Ok(Rendered {
+                    content,
+                    ..Default::default()
+                })
+            }
+        };
+
+        let diff = match options.check || options.diff {
+            true => output::diff_file(path, &content)?,
+            false => None,
+        };
+        let written = match options.check {
+            true => false,
+            false => output::write_file(path, &content, options.mode)?,
+        };
+
+        Ok(Rendered {
+            content,
+            written,
+            diff,
+        })
+    }
+
+    /// Create the diagnostic of the error from rendering the root template.
+    pub fn diagnose(
+        &self,
+        error: &handlebars::RenderError,
+    ) -> Diagnostic {
+        Diagnostic::from_render_error(
+            error,
+            &self.registry,
+            &self.templates,
+            &self.root,
+            &self.data,
+        )
+    }
+
+    fn locals(
+        &self,
+        item: &Item,
+    ) -> BTreeMap<String, serde_json::Value> {
+        let mut locals = item.locals.clone();
+        locals.insert("meta".to_string(), self.front_matter.values.clone());
+        locals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn render_to_string_test() {
+        let rendered = Renderer::new()
+            .template_path("tests/template.hbs")
+            .data_file("tests/default.toml")
+            .data_value(toml::from_str("[database]\nurl = 'sqlite://'").unwrap())
+            .strict(true)
+            .render_to_string()
+            .unwrap();
+        assert_eq!(rendered, "sqlite:// is the URL.");
+
+        let error = Renderer::new()
+            .template_path("tests/template.hbs")
+            .root("ghost")
+            .render_to_string()
+            .unwrap_err();
+        assert!(matches!(error, Error::TemplateNotFound(_)));
+    }
+
+    #[test]
+    fn require_data_files_test() {
+        let renderer = Renderer::new()
+            .template_path("tests/template.hbs")
+            .data_file("tests/ghost.toml")
+            .data_file("tests/default.toml");
+
+        // The missing data file is skipped with its error by default.
+        let prepared = renderer.prepare().unwrap();
+        assert_eq!(prepared.skipped_data.len(), 1);
+        assert!(matches!(
+            &prepared.skipped_data[0],
+            Error::Data { path: Some(path), .. } if path.ends_with("ghost.toml")
+        ));
+        assert!(data::lookup(&prepared.data, &["database", "url"]).is_some());
+
+        let result = renderer.require_data_files(true).prepare();
+        assert!(matches!(result, Err(Error::Data { path: Some(_), .. })));
+    }
+
+    #[test]
+    fn targets_test() {
+        let directory =
+            std::env::temp_dir().join(format!("hantemcli-targets-{}", std::process::id()));
+        let prepared = Renderer::new()
+            .template_path("tests/template.hbs")
+            .data_value(
+                toml::from_str("[database]\nurl = 'sqlite://'\n[hosts]\na = 1\nb = 2").unwrap(),
+            )
+            .prepare()
+            .unwrap();
+
+        let targets = prepared
+            .targets(Some("hosts"), Some(&directory.join("{{@key}}.txt")), None)
+            .unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[1].path, Some(directory.join("b.txt")));
+
+        // Each item should have its own output file.
+        let error = prepared
+            .targets(Some("hosts"), Some(&directory.join("all.txt")), None)
+            .unwrap_err();
+        assert!(matches!(error, Error::OutputPath(_)));
+
+        let target = Target {
+            item: Item::default(),
+            path: Some(directory.join("a.txt")),
+        };
+        let check = WriteOptions {
+            check: true,
+            ..Default::default()
+        };
+        let rendered = prepared.render_target(&target, &check).unwrap();
+        assert_eq!(rendered.content, "sqlite:// is the URL.");
+        assert!(!rendered.written);
+        assert!(rendered.diff.is_some());
+        assert!(!directory.join("a.txt").exists());
+
+        let rendered = prepared
+            .render_target(&target, &WriteOptions::default())
+            .unwrap();
+        assert!(rendered.written);
+        let rendered = prepared.render_target(&target, &check).unwrap();
+        assert_eq!(rendered.diff, None);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
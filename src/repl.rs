@@ -1,28 +1,64 @@
-use std::convert::{TryFrom, TryInto};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
 use std::env;
 use std::error::Error;
-use std::fmt::Debug;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::fmt::{Debug, Display};
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
 
-use config::{self, Source};
-use handlebars;
-use toml;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
 
-use crate::templates;
+use hantemcli::data::DataLayer;
+use hantemcli::output::{self, LineEnding, Newline, OutputFormat};
+use hantemcli::templates::{self, EscapeMode, NameStyle, RegisteredTemplate, TemplateFilter};
+use hantemcli::{data, variables};
+
+use crate::exit::{ExitStatus, Failure};
+use crate::shell;
 
 static HELP_STRING: &str = "The Hantemcli has a few subcommands to evaluate. 
 
-* add [data | template] FILES... - add the data/template in the respective cache
+* add [data | template] FILES... - add the data/template in the respective cache (glob patterns are expanded)
 * cd PATH - change the current working directory of the process
+* config [OPTION [VALUE]] - view or change the options (strict, extension, escape, prompt, newline, line-ending, bom, trim)
+* eval TEMPLATE - render the template string with the data and the registered partials
+* eval - render a multi-line template string ended with a line of a single '.'
 * exit - exit the REPL
 * help - view the help section
 * render KEY - render the template with the data
 * reset [data | template] - clear the data/template cache
-* view [data | template] KEY - view the containing template string/data of the key
+* view data [KEY] [--format toml | json | yaml] - view the value at the key (e.g., services[0].name) or the whole data
+* view template [KEY] - view the template of the key or list the template names
+* write KEY PATH [--append] - render the template into the file (or append to the end of it)
 * pwd - print the current working directory of the process
+* reload - read all of the template and data sources again and show the changes
+* sources - list the template and data sources in the order they are added
+
+Press Tab to complete the commands, template names, data keys, and file paths.
 ";
 
+/// The commands of the REPL to be completed.
+static COMMANDS: &[&str] = &[
+    "add", "cd", "config", "eval", "exit", "help", "pwd", "reload", "render", "reset", "sources",
+    "view", "write",
+];
+
+/// The output formats of the data to be completed.
+static DATA_FORMATS: &[&str] = &["json", "toml", "yaml"];
+
+/// The prompt for the lines of the multi-line input.
+static MULTILINE_PROMPT: &str = ". ";
+
+/// The keywords of the commands with a type (i.e., `add`, `reset`, `view`) to be completed.
+static TYPES: &[&str] = &["data", "template"];
+
 #[derive(Debug)]
 pub enum Type {
     Data,
@@ -41,14 +77,141 @@ impl TryFrom<&str> for Type {
     }
 }
 
+/// The format of the data shown with the `view` command.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DataFormat {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl TryFrom<&str> for DataFormat {
+    type Error = String;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        match string {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            _ => Err(format!("No such data format as {}", string)),
+        }
+    }
+}
+
+impl DataFormat {
+    /// Format the value where the values other than a table are shown inline in TOML.
+    pub fn format(
+        self,
+        value: &toml::Value,
+    ) -> Result<String, Box<dyn Error>> {
+        let formatted = match self {
+            Self::Toml => match value {
+                toml::Value::Table(_) => toml::to_string_pretty(value)?,
+                _ => value.to_string(),
+            },
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Yaml => serde_yaml::to_string(value)?,
+        };
+
+        Ok(formatted.trim_end().to_string())
+    }
+}
+
+/// The options of the session changed with the `config` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplOption {
+    Strict,
+
+    /// The file extensions of the templates searched in the directories separated by commas.
+    Extension,
+    Escape,
+    Prompt,
+    Newline,
+    LineEnding,
+    Bom,
+    Trim,
+}
+
+impl TryFrom<&str> for ReplOption {
+    type Error = String;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        match string {
+            "strict" => Ok(Self::Strict),
+            "extension" => Ok(Self::Extension),
+            "escape" => Ok(Self::Escape),
+            "prompt" => Ok(Self::Prompt),
+            "newline" => Ok(Self::Newline),
+            "line-ending" => Ok(Self::LineEnding),
+            "bom" => Ok(Self::Bom),
+            "trim" => Ok(Self::Trim),
+            _ => Err(format!("No such option as {:?}", string)),
+        }
+    }
+}
+
+impl ReplOption {
+    pub const ALL: &'static [Self] = &[
+        Self::Strict,
+        Self::Extension,
+        Self::Escape,
+        Self::Prompt,
+        Self::Newline,
+        Self::LineEnding,
+        Self::Bom,
+        Self::Trim,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Extension => "extension",
+            Self::Escape => "escape",
+            Self::Prompt => "prompt",
+            Self::Newline => "newline",
+            Self::LineEnding => "line-ending",
+            Self::Bom => "bom",
+            Self::Trim => "trim",
+        }
+    }
+
+    /// The possible values of the option to be completed.
+    fn values(self) -> &'static [&'static str] {
+        match self {
+            Self::Strict | Self::Bom | Self::Trim => &["false", "true"],
+            Self::Escape => &["html", "none"],
+            Self::Newline => &["add", "keep", "strip"],
+            Self::LineEnding => &["crlf", "lf", "none"],
+            Self::Extension | Self::Prompt => &[],
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ReplCommand {
     Add(Type, Vec<PathBuf>),
     Reset(Type),
-    View(Type, String),
+    /// View the value at the key or everything if there's no key.
+    View(Type, Option<String>, DataFormat),
     Render(String),
+
+    /// Render the template string (or the multi-line input if it's empty).
+    Eval(String),
+
+    /// View all of the options, view the option, or set the option to the value.
+    Config(Option<ReplOption>, Option<String>),
+
+    /// Render the template into the file at the path.
+    Write {
+        key: String,
+        path: PathBuf,
+        append: bool,
+    },
     ChangeDirectory(String),
     Pwd, // Present working directory
+    Reload,
+    Sources,
     Help,
     Exit,
 }
@@ -57,7 +220,15 @@ impl TryFrom<&str> for ReplCommand {
     type Error = String;
 
     fn try_from(string: &str) -> Result<Self, Self::Error> {
-        let mut args = string.split_whitespace();
+        // The template string of `eval` is taken as is without the shell-like parsing.
+        if let Some(template) = string.trim_start().strip_prefix("eval") {
+            if template.is_empty() || template.starts_with(char::is_whitespace) {
+                return Ok(Self::Eval(template.trim_start().to_string()));
+            }
+        }
+
+        let words = shell::split(string).map_err(|e| e.show(string))?;
+        let mut args = words.iter().map(|v| v.value.as_str());
         let command = match args.next() {
             Some(c) => c,
             None => return Err("No command given.".to_string()),
@@ -71,10 +242,8 @@ impl TryFrom<&str> for ReplCommand {
                 };
 
                 let subcommand = Type::try_from(_type)?;
-                Ok(Self::Add(
-                    subcommand,
-                    args.map(|arg| PathBuf::from(arg)).collect(),
-                ))
+                let paths = shell::expand_paths(&words[2..]).map_err(|e| e.show(string))?;
+                Ok(Self::Add(subcommand, paths))
             }
             "reset" => {
                 let _type = match args.next() {
@@ -89,43 +258,202 @@ impl TryFrom<&str> for ReplCommand {
                 let _type = args.next().ok_or("No subcommand given".to_string())?;
 
                 let subcommand = Type::try_from(_type)?;
-                let key = args
-                    .next()
-                    .and_then(|v| Some(v.to_string()))
-                    .ok_or("No key given.".to_string())?;
-                Ok(Self::View(subcommand, key))
+                let mut key = None;
+                let mut format = DataFormat::default();
+                while let Some(arg) = args.next() {
+                    match arg {
+                        "--format" | "-f" => {
+                            let value = args.next().ok_or("No format given.".to_string())?;
+                            format = DataFormat::try_from(value)?;
+                        }
+                        _ if key.is_none() => key = Some(arg.to_string()),
+                        _ => return Err(format!("Unexpected argument {:?}", arg)),
+                    }
+                }
+
+                Ok(Self::View(subcommand, key, format))
             }
             "render" => {
                 let key = args
                     .next()
-                    .and_then(|v| Some(v.to_string()))
+                    .map(|v| v.to_string())
                     .ok_or("No key given.".to_string())?;
 
                 Ok(Self::Render(key))
             }
+            "write" => {
+                let mut positional = vec![];
+                let mut append = false;
+                for arg in args {
+                    match arg {
+                        "--append" | "-a" => append = true,
+                        _ => positional.push(arg),
+                    }
+                }
+
+                match positional.as_slice() {
+                    [key, path] => Ok(Self::Write {
+                        key: key.to_string(),
+                        path: PathBuf::from(path),
+                        append,
+                    }),
+                    [] | [_] => Err("No key or path given.".to_string()),
+                    _ => Err("Only a key and a path are expected.".to_string()),
+                }
+            }
+            "config" | "set-option" => {
+                let option = args.next().map(ReplOption::try_from).transpose()?;
+                let value = args.next().map(|v| v.to_string());
+                if args.next().is_some() {
+                    return Err("Only an option and a value are expected.".to_string());
+                }
+
+                Ok(Self::Config(option, value))
+            }
             "cd" => {
-                let path = args.next().and_then(|v| Some(v.to_string())).ok_or("No path given.".to_string())?;
+                let path = args
+                    .next()
+                    .map(|v| v.to_string())
+                    .ok_or("No path given.".to_string())?;
 
                 Ok(Self::ChangeDirectory(path))
             }
             "pwd" => Ok(Self::Pwd),
             "help" | "?" => Ok(Self::Help),
             "exit" => Ok(Self::Exit),
+            "reload" => Ok(Self::Reload),
+            "sources" => Ok(Self::Sources),
             _ => Err(format!("No such keyword as {:?}", string)),
         }
     }
 }
 
-pub enum ReplError<'s> {
-    ReadError(&'s str),
-    EvalError(&'s str),
+/// The helper of the line editor completing the commands, the keywords, the template names, the
+/// data keys, and the file paths (for `add` and `cd`).
+#[derive(Default)]
+pub struct ReplHelper {
+    /// The names of the registered templates.
+    pub template_names: Vec<String>,
+
+    /// The dotted paths of the data (e.g., `database.url`).
+    pub data_keys: Vec<String>,
+
+    filename_completer: FilenameCompleter,
+}
+
+impl ReplHelper {
+    /// Get the start of the word at the position of the line and the candidates to complete it
+    /// except for the file paths.
+    pub fn candidates(
+        &self,
+        line: &str,
+        pos: usize,
+    ) -> (usize, Vec<String>) {
+        let start = word_start(line, pos);
+        let word = &line[start..pos];
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let candidates: Vec<String> = match words.as_slice() {
+            [] => COMMANDS.iter().map(|v| v.to_string()).collect(),
+            ["add"] | ["reset"] | ["view"] => TYPES.iter().map(|v| v.to_string()).collect(),
+            ["render"] | ["write"] | ["view", "template"] | ["view", "templates"] => {
+                self.template_names.clone()
+            }
+            ["view", "data"] => self.data_keys.clone(),
+            ["config"] | ["set-option"] => ReplOption::ALL
+                .iter()
+                .map(|v| v.name().to_string())
+                .collect(),
+            ["config", option] | ["set-option", option] => match ReplOption::try_from(*option) {
+                Ok(option) => option.values().iter().map(|v| v.to_string()).collect(),
+                Err(_) => vec![],
+            },
+            ["view", "data", .., "--format"] | ["view", "data", .., "-f"] => {
+                DATA_FORMATS.iter().map(|v| v.to_string()).collect()
+            }
+            _ => vec![],
+        };
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+
+        (start, candidates)
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = word_start(line, pos);
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+        if matches!(words.as_slice(), ["add", _, ..] | ["cd"] | ["write", _]) {
+            return self.filename_completer.complete_path(line, pos);
+        }
+
+        let (start, candidates) = self.candidates(line, pos);
+        let candidates = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
 }
 
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
 pub struct Repl {
     pub template_registry: handlebars::Handlebars<'static>,
     pub data: config::Config,
     pub prompt: String,
-    pub file_extension: String,
+    pub template_filter: TemplateFilter,
+    pub name_style: NameStyle,
+
+    /// The escaping of the values kept for the new template registries.
+    pub escape: EscapeMode,
+
+    /// The formatting, permissions, and root directory of the output files the same as the
+    /// command line.
+    pub output_format: OutputFormat,
+    pub mode: Option<u32>,
+    pub output_root: Option<PathBuf>,
+
+    /// The file where the history of the commands is saved across sessions.
+    pub history_path: Option<PathBuf>,
+
+    /// The template paths and the data layers added in order to be read again with `reload`.
+    pub template_paths: Vec<PathBuf>,
+    pub data_layers: Vec<DataLayer>,
+
+    /// The registered templates to check the names of the templates added later.
+    pub templates: Vec<RegisteredTemplate>,
+
+    /// Continue with the successfully registered templates if some of the template files failed
+    /// to be added or reloaded the same as the command line.
+    pub keep_going: bool,
+
+    /// Whether the commands are run from a script which prints the status messages in the
+    /// standard error to keep the standard output for the rendered output.
+    pub script: bool,
 }
 
 impl Default for Repl {
@@ -134,11 +462,126 @@ impl Default for Repl {
             template_registry: handlebars::Handlebars::new(),
             data: config::Config::new(),
             prompt: "> ".to_string(),
-            file_extension: "hbs".to_string(),
+            template_filter: TemplateFilter::default(),
+            name_style: NameStyle::default(),
+            escape: EscapeMode::Html,
+            output_format: OutputFormat::default(),
+            mode: None,
+            output_root: None,
+            history_path: default_history_path(),
+            template_paths: vec![],
+            data_layers: vec![],
+            templates: vec![],
+            keep_going: false,
+            script: false,
         }
     }
 }
 
+/// Get the values of the data that are not a table or an array (or an empty one) with their path
+/// (e.g., `services[0].name`).
+fn flatten_data(data: &toml::Value) -> BTreeMap<String, toml::Value> {
+    let mut values = BTreeMap::new();
+    collect_values(data, String::new(), &mut values);
+    values
+}
+
+fn collect_values(
+    value: &toml::Value,
+    path: String,
+    values: &mut BTreeMap<String, toml::Value>,
+) {
+    match value {
+        toml::Value::Table(table) if !table.is_empty() => {
+            for (key, value) in table.iter() {
+                let path = match path.is_empty() {
+                    true => key.to_string(),
+                    false => format!("{}.{}", path, key),
+                };
+                collect_values(value, path, values);
+            }
+        }
+        toml::Value::Array(array) if !array.is_empty() => {
+            for (index, value) in array.iter().enumerate() {
+                collect_values(value, format!("{}[{}]", path, index), values);
+            }
+        }
+        _ => {
+            values.insert(path, value.clone());
+        }
+    }
+}
+
+/// Get the start of the word at the position of the line.
+fn word_start(
+    line: &str,
+    pos: usize,
+) -> usize {
+    line[..pos]
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0)
+}
+
+/// Read the lines of the multi-line input until a line with a single `.` (or the end of the
+/// input).
+/// Returns `None` if the input is cancelled with Ctrl+C.
+fn read_multiline_input(
+    editor: &mut Editor<ReplHelper, DefaultHistory>
+) -> rustyline::Result<Option<String>> {
+    let mut lines = vec![];
+    loop {
+        match editor.readline(MULTILINE_PROMPT) {
+            Ok(line) if line == "." => break,
+            Ok(line) => lines.push(line),
+            Err(ReadlineError::Eof) => break,
+            Err(ReadlineError::Interrupted) => return Ok(None),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Some(lines.join("\n")))
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "on" => Ok(true),
+        "false" | "off" => Ok(false),
+        _ => Err(format!("Expected true or false but got {:?}", value)),
+    }
+}
+
+fn set_escape(
+    registry: &mut handlebars::Handlebars,
+    escape: EscapeMode,
+) {
+    match escape {
+        EscapeMode::Html => registry.register_escape_fn(handlebars::html_escape),
+        EscapeMode::None => registry.register_escape_fn(handlebars::no_escape),
+    }
+}
+
+/// Get the file of the REPL history in the data directory of the user (i.e.,
+/// `$XDG_DATA_HOME/hantemcli/history`, `~/.local/share/hantemcli/history`).
+/// It can be set with the `HANTEMCLI_HISTORY` environment variable where an empty value disables
+/// the history file.
+fn default_history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("HANTEMCLI_HISTORY") {
+        return match path.is_empty() {
+            true => None,
+            false => Some(PathBuf::from(path)),
+        };
+    }
+
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|v| PathBuf::from(v).join(".local/share")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(data_dir.join("hantemcli").join("history"))
+}
+
 impl Repl {
     pub fn _loop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         println!(
@@ -146,26 +589,395 @@ impl Repl {
         );
         println!("Enter the command 'help' for more information.\n");
 
+        let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new()?;
+        editor.set_helper(Some(ReplHelper::default()));
+        if let Some(history_path) = &self.history_path {
+            // The history file is missing on the first session.
+            editor.load_history(history_path).ok();
+        }
+
         loop {
-            print!("{}", self.prompt);
+            if let Some(helper) = editor.helper_mut() {
+                self.update_helper(helper);
+            }
 
-            io::stdout().flush().unwrap();
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
+            let input = match editor.readline(&self.prompt) {
+                Ok(v) => v,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            if !input.trim().is_empty() {
+                editor.add_history_entry(input.as_str())?;
+            }
 
             let command = match self.read(input) {
                 Ok(v) => match v {
-                    ReplCommand::Exit => return Ok(()),
+                    ReplCommand::Exit => break,
+                    ReplCommand::Eval(template) if template.is_empty() => {
+                        match read_multiline_input(&mut editor)? {
+                            Some(v) => ReplCommand::Eval(v),
+                            None => continue,
+                        }
+                    }
                     _ => v,
                 },
-                Err(_e) => continue,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
             };
 
             match self.eval(command) {
                 Ok(_v) => continue,
-                Err(e) => eprintln!("{:?}", e),
+                Err(e) => eprintln!("{}", e),
+            };
+        }
+
+        if let Some(history_path) = &self.history_path {
+            let result = match history_path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+                _ => Ok(()),
+            }
+            .map_err(ReadlineError::from)
+            .and_then(|_| editor.save_history(history_path));
+
+            if let Err(e) = result {
+                eprintln!("Cannot save the history to {:?}: {}", history_path, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the commands from the reader (e.g., a script file, the piped standard input) without
+    /// the banner and the prompt.
+    /// Empty lines and lines starting with `#` are skipped.
+    /// If `echo` is set, each command is printed in the standard error before running it.
+    ///
+    /// It stops at the first command that failed with the error along with its line number.
+    pub fn run_script<R: BufRead>(
+        &mut self,
+        reader: R,
+        echo: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        // The errors from reading the script are not from writing any output.
+        let read_error = |e: std::io::Error| {
+            Failure::new(ExitStatus::Usage, format!("Cannot read the script: {}", e))
+        };
+
+        self.script = true;
+
+        let mut lines = reader.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line = line.map_err(read_error)?;
+            let trimmed_line = line.trim();
+            if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+                continue;
+            }
+
+            if echo {
+                eprintln!("{}{}", self.prompt, line);
+            }
+
+            let script_error = |status: ExitStatus, e: Box<dyn Error>| -> Box<dyn Error> {
+                Box::new(Failure::new(
+                    status,
+                    // The errors pointing at the column start on their own line to keep the
+                    // caret under the line.
+                    match e.to_string().contains('\n') {
+                        true => format!("Line {}:\n{}", index + 1, e),
+                        false => format!("Line {}: {}", index + 1, e),
+                    },
+                ))
+            };
+
+            // The commands that cannot be parsed are misuses of the script.
+            let command = match self
+                .read(line)
+                .map_err(|e| script_error(ExitStatus::Usage, e.into()))?
+            {
+                ReplCommand::Exit => break,
+                ReplCommand::Eval(template) if template.is_empty() => {
+                    let mut template_lines = vec![];
+                    for (_, line) in lines.by_ref() {
+                        let line = line.map_err(read_error)?;
+                        if echo {
+                            eprintln!("{}{}", MULTILINE_PROMPT, line);
+                        }
+
+                        if line == "." {
+                            break;
+                        }
+                        template_lines.push(line);
+                    }
+
+                    ReplCommand::Eval(template_lines.join("\n"))
+                }
+                command => command,
             };
+
+            self.eval(command)
+                .map_err(|e| script_error(ExitStatus::from_error(e.as_ref()), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the template string with the data.
+    /// The registered templates are available as partials.
+    pub fn eval_template(
+        &self,
+        template: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let data: toml::Value = self.data.clone().try_into()?;
+
+        Ok(self.template_registry.render_template(template, &data)?)
+    }
+
+    /// Create an empty template registry with the same settings.
+    fn new_registry(&self) -> handlebars::Handlebars<'static> {
+        let mut registry = handlebars::Handlebars::new();
+        registry.set_strict_mode(self.template_registry.strict_mode());
+        set_escape(&mut registry, self.escape);
+        registry
+    }
+
+    /// Get the value of the option as shown and set with the `config` command.
+    fn option_value(
+        &self,
+        option: ReplOption,
+    ) -> String {
+        let output_format = &self.output_format;
+        match option {
+            ReplOption::Strict => self.template_registry.strict_mode().to_string(),
+            ReplOption::Extension => self
+                .template_filter
+                .extensions
+                .iter()
+                .map(|v| v.trim_start_matches('.'))
+                .collect::<Vec<&str>>()
+                .join(","),
+            ReplOption::Escape => match self.escape {
+                EscapeMode::Html => "html",
+                EscapeMode::None => "none",
+            }
+            .to_string(),
+            ReplOption::Prompt => format!("{:?}", self.prompt),
+            ReplOption::Newline => match output_format.newline {
+                Newline::Keep => "keep",
+                Newline::Add => "add",
+                Newline::Strip => "strip",
+            }
+            .to_string(),
+            ReplOption::LineEnding => match output_format.line_ending {
+                Some(LineEnding::Lf) => "lf",
+                Some(LineEnding::Crlf) => "crlf",
+                None => "none",
+            }
+            .to_string(),
+            ReplOption::Bom => output_format.bom.to_string(),
+            ReplOption::Trim => output_format.trim.to_string(),
+        }
+    }
+
+    fn set_option(
+        &mut self,
+        option: ReplOption,
+        value: &str,
+    ) -> Result<(), String> {
+        match option {
+            ReplOption::Strict => self.template_registry.set_strict_mode(parse_flag(value)?),
+            ReplOption::Extension => {
+                let extensions: Vec<String> = value
+                    .split(',')
+                    .map(|v| v.trim().trim_start_matches('.'))
+                    .filter(|v| !v.is_empty())
+                    .map(|v| format!(".{}", v))
+                    .collect();
+                if extensions.is_empty() {
+                    return Err("No file extension given.".to_string());
+                }
+
+                self.template_filter.extensions = extensions;
+            }
+            ReplOption::Escape => {
+                self.escape = value.parse()?;
+                set_escape(&mut self.template_registry, self.escape);
+            }
+            ReplOption::Prompt => self.prompt = value.to_string(),
+            ReplOption::Newline => self.output_format.newline = value.parse()?,
+            ReplOption::LineEnding => {
+                self.output_format.line_ending = match value {
+                    "none" => None,
+                    _ => Some(value.parse()?),
+                }
+            }
+            ReplOption::Bom => self.output_format.bom = parse_flag(value)?,
+            ReplOption::Trim => self.output_format.trim = parse_flag(value)?,
+        }
+
+        Ok(())
+    }
+
+    /// Read all of the template paths and the data layers again then print the changes.
+    /// If some of the template files failed to be registered (unless with `keep_going`) or the
+    /// data cannot be merged, the templates and the data are kept as they were.
+    fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut registry = self.new_registry();
+        let registration = templates::register_from_path(
+            &mut registry,
+            self.template_paths.clone(),
+            &self.template_filter,
+            self.name_style,
+        )
+        .into_result(self.keep_going)
+        .map_err(hantemcli::Error::from)?;
+        let (data, skipped_data) = data::merge_layers(&self.data_layers, false)?;
+
+        for failure in registration.failures.iter() {
+            eprintln!("{}", failure);
+        }
+        for error in skipped_data.iter() {
+            eprintln!("{}", error);
+        }
+
+        let mut changes = vec![];
+        let old_templates = self.template_registry.get_templates();
+        let new_templates = registry.get_templates();
+        for (name, template) in new_templates.iter() {
+            match old_templates.get(name) {
+                Some(old_template) if old_template == template => (),
+                Some(_) => changes.push(format!("The template {:?} has changed.", name)),
+                None => changes.push(format!("The template {:?} has been added.", name)),
+            }
+        }
+        for name in old_templates.keys() {
+            if !new_templates.contains_key(name) {
+                changes.push(format!("The template {:?} has been removed.", name));
+            }
+        }
+
+        let old_values = flatten_data(&self.data.clone().try_into()?);
+        let new_values = flatten_data(&data.clone().try_into()?);
+        for (key, value) in new_values.iter() {
+            match old_values.get(key) {
+                Some(old_value) if old_value == value => (),
+                Some(_) => changes.push(format!("The data key {:?} has changed.", key)),
+                None => changes.push(format!("The data key {:?} has been added.", key)),
+            }
+        }
+        for key in old_values.keys() {
+            if !new_values.contains_key(key) {
+                changes.push(format!("The data key {:?} has been removed.", key));
+            }
+        }
+
+        self.template_registry = registry;
+        self.templates = registration.templates;
+        self.data = data;
+
+        changes.sort();
+        for change in changes.iter() {
+            self.status(change);
+        }
+        self.status(format!(
+            "Reloaded {} template source(s) and {} data source(s) with {} change(s).",
+            self.template_paths.len(),
+            self.data_layers.len(),
+            changes.len()
+        ));
+
+        Ok(())
+    }
+
+    /// Render the template and the output path (as a template) with the data.
+    /// The rendered template is formatted the same as the command line.
+    fn render_output(
+        &self,
+        key: &str,
+        path: &Path,
+    ) -> Result<(PathBuf, String), Box<dyn Error>> {
+        let data: toml::Value = self.data.clone().try_into()?;
+        let content = output::format(
+            &self.template_registry.render(key, &data)?,
+            &self.output_format,
+        );
+        let path = output::render_path(
+            path,
+            &data,
+            &[],
+            &BTreeMap::new(),
+            self.template_registry.strict_mode(),
+            self.output_root.as_deref(),
+        )?;
+
+        Ok((path, content))
+    }
+
+    /// Print the message about the result of a command.
+    /// While running a script, it is printed in the standard error instead.
+    fn status<D: Display>(
+        &self,
+        message: D,
+    ) {
+        match self.script {
+            true => eprintln!("{}", message),
+            false => println!("{}", message),
+        }
+    }
+
+    /// Render the template into the file at the output path (as a template) then get the message
+    /// of the result.
+    fn write(
+        &self,
+        key: &str,
+        path: &Path,
+        append: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let (path, content) = self.render_output(key, path)?;
+
+        let message = match append {
+            true => {
+                let length = output::append_file(&path, &content, self.mode)?;
+                format!("Appended {} bytes to {:?}.", length, path)
+            }
+            false => match output::write_file(&path, &content, self.mode)? {
+                true => format!("Wrote {} bytes to {:?}.", content.len(), path),
+                false => format!(
+                    "The file {:?} is unchanged ({} bytes).",
+                    path,
+                    content.len()
+                ),
+            },
+        };
+
+        Ok(message)
+    }
+
+    /// Update the completion candidates with the current templates and data.
+    fn update_helper(
+        &self,
+        helper: &mut ReplHelper,
+    ) {
+        helper.template_names = self
+            .template_registry
+            .get_templates()
+            .keys()
+            .cloned()
+            .collect();
+        helper.template_names.sort();
+
+        let mut data_keys = BTreeSet::new();
+        if let Ok(data) = self.data.clone().try_into::<toml::Value>() {
+            for leaf in data::leaf_paths(&data) {
+                for length in 1..=leaf.len() {
+                    data_keys.insert(variables::dotted_path(&leaf[..length]));
+                }
+            }
         }
+        helper.data_keys = data_keys.into_iter().collect();
     }
 
     pub fn read(
@@ -186,77 +998,127 @@ impl Repl {
             ReplCommand::Add(item, paths) => match item {
                 Type::Data => {
                     // Getting the data from the files.
+                    let mut failures = 0;
                     for data_path in paths.iter() {
                         match self.data.merge(
-                            config::File::with_name(&data_path.to_string_lossy()).required(false),
+                            config::File::with_name(&data_path.to_string_lossy()).required(true),
                         ) {
-                            Ok(_v) => println!(
-                                "The data within the path {:?} has been merged.",
-                                data_path
-                            ),
-                            Err(e) => eprintln!(
-                                "An error occurred for the data file {:?}\n{}\n",
-                                data_path, e
-                            ),
+                            Ok(_v) => {
+                                self.status(format!(
+                                    "The data within the path {:?} has been merged.",
+                                    data_path
+                                ));
+                                self.data_layers
+                                    .push(DataLayer::File(data_path.to_path_buf()));
+                            }
+                            Err(e) => {
+                                eprintln!(
+                                    "An error occurred for the data file {:?}\n{}\n",
+                                    data_path, e
+                                );
+                                failures += 1;
+                            }
                         }
                     }
+
+                    if failures > 0 {
+                        return Err(Box::new(Failure::new(
+                            ExitStatus::Data,
+                            format!("{} data file(s) failed to be merged.", failures),
+                        )));
+                    }
                 }
                 Type::TemplateRegistry => {
-                    let registered_paths = templates::register_from_path(
-                        &mut self.template_registry,
-                        paths,
-                        &self.file_extension,
-                    )?;
-
-                    for registered_file in registered_paths {
-                        println!(
+                    // The templates are registered into the session only after all of them (or
+                    // some with `keep_going`) are successfully registered into a new registry.
+                    let registration = templates::register_more_from_path(
+                        &mut self.new_registry(),
+                        &self.templates,
+                        paths.clone(),
+                        &self.template_filter,
+                        self.name_style,
+                    )
+                    .into_result(self.keep_going)
+                    .map_err(hantemcli::Error::from)?;
+
+                    for registered_template in registration.templates {
+                        templates::register_template_file(
+                            &mut self.template_registry,
+                            &registered_template.name,
+                            &registered_template.path,
+                        )?;
+                        self.status(format!(
                             "The template file {:?} has successfully registered.",
-                            registered_file
-                        );
+                            registered_template.path
+                        ));
+                        self.templates
+                            .retain(|t| t.name != registered_template.name);
+                        self.templates.push(registered_template);
+                    }
+                    self.template_paths.extend(paths);
+
+                    for failure in registration.failures.iter() {
+                        eprintln!("{}", failure);
                     }
                 }
             },
             ReplCommand::Reset(item) => match item {
                 Type::Data => {
                     self.data = config::Config::new();
-                    println!("The data table has been cleared.");
+                    self.data_layers.clear();
+                    self.status("The data table has been cleared.");
                 }
                 Type::TemplateRegistry => {
-                    self.template_registry = handlebars::Handlebars::new();
-                    println!("The template registry has been cleared.");
+                    self.template_registry = self.new_registry();
+                    self.template_paths.clear();
+                    self.templates.clear();
+                    self.status("The template registry has been cleared.");
                 }
             },
-            ReplCommand::View(item, key) => match item {
+            ReplCommand::View(item, key, format) => match item {
                 Type::Data => {
-                    let data: toml::Value = self.data.clone()
-                        .try_into()
-                        .expect("The resulting TOML has an value error occurred... That shouldn't happened.");
+                    let data: toml::Value = self.data.clone().try_into()?;
+                    let path = match &key {
+                        Some(key) => data::parse_path(key)?,
+                        None => vec![],
+                    };
 
-                    match data.get(key) {
-                        Some(v) => println!("{}", toml::to_string_pretty(v)?),
-                        None => eprintln!("There's no value for the given key."),
+                    match data::lookup(&data, &path) {
+                        Some(v) => println!("{}", format.format(v)?),
+                        None => return Err("There's no value for the given key.".into()),
                     }
                 }
                 Type::TemplateRegistry => {
                     let template_store = self.template_registry.get_templates();
 
-                    match template_store.get(&key) {
-                        Some(v) => println!("{:?}", v),
-                        None => eprintln!("There's no template for the given key."),
+                    match key {
+                        Some(key) => match template_store.get(&key) {
+                            Some(v) => println!("{:?}", v),
+                            None => return Err("There's no template for the given key.".into()),
+                        },
+                        None => {
+                            let mut names: Vec<&String> = template_store.keys().collect();
+                            names.sort();
+                            for name in names {
+                                println!("{}", name);
+                            }
+                        }
                     }
                 }
             },
             ReplCommand::ChangeDirectory(path) => {
-                match env::set_current_dir(&path) {
-                    Ok(_v) => println!("Changed to {:?} successfully", path),
-                    Err(e) => eprintln!("{}", e),
-                }
-            },
+                env::set_current_dir(&path).map_err(|e| {
+                    Failure::new(
+                        ExitStatus::Usage,
+                        format!("Cannot change the directory to {:?}: {}", path, e),
+                    )
+                })?;
+                self.status(format!("Changed to {:?} successfully", path));
+            }
             ReplCommand::Pwd => {
-                match env::current_dir() {
-                    Ok(v) => println!("{:?}", v),
-                    Err(e) => eprintln!("{}", e),
-                }
+                let current_dir = env::current_dir()
+                    .map_err(|e| Failure::new(ExitStatus::Failure, e.to_string()))?;
+                println!("{:?}", current_dir);
             }
             ReplCommand::Render(key) => {
                 let rendered_string = self
@@ -265,9 +1127,327 @@ impl Repl {
 
                 println!("{}", rendered_string);
             }
+            ReplCommand::Write { key, path, append } => {
+                self.status(self.write(&key, &path, append)?)
+            }
+            ReplCommand::Config(option, value) => match (option, value) {
+                (None, _) => {
+                    for option in ReplOption::ALL.iter() {
+                        println!("{} = {}", option.name(), self.option_value(*option));
+                    }
+                }
+                (Some(option), None) => {
+                    println!("{} = {}", option.name(), self.option_value(option))
+                }
+                (Some(option), Some(value)) => {
+                    self.set_option(option, &value)?;
+                    self.status(format!(
+                        "The option {} has been set to {}.",
+                        option.name(),
+                        self.option_value(option)
+                    ));
+                }
+            },
+            ReplCommand::Reload => self.reload()?,
+            ReplCommand::Sources => {
+                println!("Templates:");
+                for (index, path) in self.template_paths.iter().enumerate() {
+                    println!("  {}. {}", index + 1, path.display());
+                }
+
+                println!("Data:");
+                for (index, layer) in self.data_layers.iter().enumerate() {
+                    let source = match layer {
+                        DataLayer::File(path) => path.display().to_string(),
+                        DataLayer::Value(_) => "a value".to_string(),
+                        DataLayer::Environment { prefix: None } => {
+                            "the environment variables".to_string()
+                        }
+                        DataLayer::Environment {
+                            prefix: Some(prefix),
+                        } => format!("the environment variables with the prefix {:?}", prefix),
+                    };
+                    println!("  {}. {}", index + 1, source);
+                }
+            }
+            ReplCommand::Eval(template) => println!("{}", self.eval_template(&template)?),
             _ => println!("WHAT?"),
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eval_test() {
+        let template = "{{#each animals}}{{> animal}} {{/each}}";
+        let command = ReplCommand::try_from(format!("eval {}", template).as_str()).unwrap();
+        assert!(matches!(&command, ReplCommand::Eval(v) if v == template));
+
+        let mut repl = Repl::default();
+        repl.template_registry
+            .register_template_string("animal", "<{{this}}>")
+            .unwrap();
+        repl.data
+            .merge(config::File::from_str(
+                "animals = ['cat', 'dog']",
+                config::FileFormat::Toml,
+            ))
+            .unwrap();
+        assert_eq!(repl.eval_template(template).unwrap(), "<cat> <dog> ");
+    }
+
+    #[test]
+    fn view_test() {
+        let command = ReplCommand::try_from("view data services[0].name -f json").unwrap();
+        assert!(matches!(
+            command,
+            ReplCommand::View(Type::Data, Some(ref key), DataFormat::Json) if key == "services[0].name"
+        ));
+        assert!(matches!(
+            ReplCommand::try_from("view data").unwrap(),
+            ReplCommand::View(Type::Data, None, DataFormat::Toml)
+        ));
+
+        let value = toml::Value::String("api".to_string());
+        assert_eq!(DataFormat::Toml.format(&value).unwrap(), "\"api\"");
+        assert_eq!(DataFormat::Yaml.format(&value).unwrap(), "---\napi");
+    }
+
+    #[test]
+    fn write_test() {
+        assert!(matches!(
+            ReplCommand::try_from("write base --append build/{{name}}.conf").unwrap(),
+            ReplCommand::Write { ref key, ref path, append: true }
+                if key == "base" && path == Path::new("build/{{name}}.conf")
+        ));
+        assert!(ReplCommand::try_from("write base").is_err());
+    }
+
+    #[test]
+    fn reload_test() {
+        let directory = env::temp_dir().join(format!("hantemcli-reload-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let template_path = directory.join("base.hbs");
+        let data_path = directory.join("data.toml");
+        fs::write(&template_path, "Hello {{name}}").unwrap();
+        fs::write(&data_path, "name = 'world'").unwrap();
+
+        let mut repl = Repl::default();
+        repl.eval(ReplCommand::Add(
+            Type::TemplateRegistry,
+            vec![directory.clone()],
+        ))
+        .unwrap();
+        repl.eval(ReplCommand::Add(Type::Data, vec![data_path.clone()]))
+            .unwrap();
+        assert_eq!(repl.template_paths, vec![directory.clone()]);
+        assert_eq!(repl.data_layers, vec![DataLayer::File(data_path.clone())]);
+
+        fs::write(&template_path, "Goodbye {{name}}").unwrap();
+        fs::write(&data_path, "name = 'moon'").unwrap();
+        repl.reload().unwrap();
+        assert_eq!(repl.eval_template("{{> base}}").unwrap(), "Goodbye moon");
+
+        // A template with a syntax error keeps the previous templates and data.
+        fs::write(&template_path, "Goodbye {{#if}}").unwrap();
+        fs::write(&data_path, "name = 'sun'").unwrap();
+        let error = repl.reload().unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Template);
+        assert_eq!(repl.eval_template("{{> base}}").unwrap(), "Goodbye moon");
+
+        // The template paths that failed to be added are not reloaded.
+        let other_directory = directory.join("other");
+        fs::create_dir_all(&other_directory).unwrap();
+        fs::write(other_directory.join("base.hbs"), "Hi {{name}}").unwrap();
+        let error = repl
+            .eval(ReplCommand::Add(
+                Type::TemplateRegistry,
+                vec![other_directory.clone()],
+            ))
+            .unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Template);
+        assert!(error.to_string().contains("already used by"));
+        assert_eq!(repl.template_paths, vec![directory.clone()]);
+        assert_eq!(repl.eval_template("{{> base}}").unwrap(), "Goodbye moon");
+
+        // With `keep_going`, the rest of the templates are reloaded.
+        fs::write(directory.join("greeting.hbs"), "Hey {{name}}").unwrap();
+        repl.keep_going = true;
+        repl.reload().unwrap();
+        assert_eq!(repl.eval_template("{{> greeting}}").unwrap(), "Hey sun");
+        assert!(!repl.template_registry.has_template("base"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn write_output_test() {
+        let directory = env::temp_dir().join(format!("hantemcli-write-{}", std::process::id()));
+        let output_path = directory.join("{{name}}.txt");
+
+        let mut repl = Repl::default();
+        repl.output_format.bom = true;
+        repl.template_registry
+            .register_template_string("base", "Hello {{name}}")
+            .unwrap();
+        repl.data
+            .merge(config::File::from_str(
+                "name = 'world'",
+                config::FileFormat::Toml,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            repl.write("base", &output_path, false).unwrap(),
+            format!("Wrote 14 bytes to {:?}.", directory.join("world.txt"))
+        );
+        assert_eq!(
+            repl.write("base", &output_path, false).unwrap(),
+            format!(
+                "The file {:?} is unchanged (14 bytes).",
+                directory.join("world.txt")
+            )
+        );
+
+        // The byte order mark is only at the start of the file.
+        assert_eq!(
+            repl.write("base", &output_path, true).unwrap(),
+            format!("Appended 11 bytes to {:?}.", directory.join("world.txt"))
+        );
+        assert_eq!(
+            fs::read_to_string(directory.join("world.txt")).unwrap(),
+            "\u{feff}Hello worldHello world"
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn run_script_test() {
+        let mut repl = Repl::default();
+        let script = "# Render the database URL.\nadd data tests/default.toml\n\neval\n{{database.url}}\n.\n";
+        repl.run_script(script.as_bytes(), false).unwrap();
+
+        let error = repl
+            .run_script(
+                "view data database.url\nview data ghost\nexit\n".as_bytes(),
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 2: There's no value for the given key."
+        );
+
+        // The errors from reading the input are not reported as output errors.
+        let error = repl
+            .run_script(&b"view data database.url\n\xff\n"[..], false)
+            .unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Usage);
+        let error = repl
+            .run_script("cd tests/ghost\n".as_bytes(), false)
+            .unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Usage);
+
+        // The commands that cannot be parsed are usage errors.
+        let error = repl.run_script("ghost\n".as_bytes(), false).unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Usage);
+        let error = repl
+            .run_script("add data 'tests/default.toml\n".as_bytes(), false)
+            .unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Usage);
+        assert!(error.to_string().starts_with("Line 1:\n"));
+
+        let error = repl
+            .run_script("add data tests/ghost.toml\n".as_bytes(), false)
+            .unwrap_err();
+        assert_eq!(ExitStatus::from_error(error.as_ref()), ExitStatus::Data);
+        assert_eq!(
+            error.to_string(),
+            "Line 1: 1 data file(s) failed to be merged."
+        );
+    }
+
+    #[test]
+    fn helper_candidates_test() {
+        let helper = ReplHelper {
+            template_names: vec!["base".to_string(), "partials/header".to_string()],
+            data_keys: vec!["database".to_string(), "database.url".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(helper.candidates("ren", 3), (0, vec!["render".to_string()]));
+        assert_eq!(
+            helper.candidates("view t", 6),
+            (5, vec!["template".to_string()])
+        );
+        assert_eq!(
+            helper.candidates("render p", 8),
+            (7, vec!["partials/header".to_string()])
+        );
+        assert_eq!(
+            helper.candidates("view data database.", 19),
+            (10, vec!["database.url".to_string()])
+        );
+        assert_eq!(
+            helper.candidates("config escape n", 15),
+            (14, vec!["none".to_string()])
+        );
+    }
+
+    #[test]
+    fn config_test() {
+        assert!(matches!(
+            ReplCommand::try_from("set-option prompt 'hbs> '").unwrap(),
+            ReplCommand::Config(Some(ReplOption::Prompt), Some(ref value)) if value == "hbs> "
+        ));
+        assert!(ReplCommand::try_from("config ghost").is_err());
+
+        let mut repl = Repl::default();
+        repl.data
+            .merge(config::File::from_str(
+                "name = '<world>'",
+                config::FileFormat::Toml,
+            ))
+            .unwrap();
+        assert_eq!(repl.eval_template("{{name}}").unwrap(), "&lt;world&gt;");
+
+        repl.eval(ReplCommand::Config(
+            Some(ReplOption::Escape),
+            Some("none".to_string()),
+        ))
+        .unwrap();
+        repl.eval(ReplCommand::Config(
+            Some(ReplOption::Strict),
+            Some("true".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(repl.eval_template("{{name}}").unwrap(), "<world>");
+        assert!(repl.eval_template("{{ghost}}").is_err());
+
+        // The options are kept after clearing the registry.
+        repl.eval(ReplCommand::Reset(Type::TemplateRegistry))
+            .unwrap();
+        assert_eq!(repl.eval_template("{{name}}").unwrap(), "<world>");
+        assert!(repl.eval_template("{{ghost}}").is_err());
+
+        repl.eval(ReplCommand::Config(
+            Some(ReplOption::Extension),
+            Some("hbs,.txt".to_string()),
+        ))
+        .unwrap();
+        assert_eq!(repl.template_filter.extensions, vec![".hbs", ".txt"]);
+        assert_eq!(repl.option_value(ReplOption::Extension), "hbs,txt");
+        assert!(repl
+            .eval(ReplCommand::Config(
+                Some(ReplOption::Bom),
+                Some("maybe".to_string()),
+            ))
+            .is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::data;
+use crate::variables::{self, ReferenceKind, VariableReference};
+
+/// The variable usage of the templates compared with the keys provided by the data.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// The variables referenced by each template.
+    pub references: BTreeMap<String, Vec<VariableReference>>,
+
+    /// The paths of the values in the data that are not referenced by any template.
+    pub unused: Vec<String>,
+
+    /// The references to the variables that are not provided by the data.
+    pub missing: Vec<VariableReference>,
+}
+
+impl Report {
+    /// Create the report for the given root templates.
+    ///
+    /// The unused keys are only searched from `provided_data` which is useful for excluding the
+    /// environment variables while the missing variables are searched from `data`.
+    pub fn new(
+        registry: &handlebars::Handlebars,
+        roots: &[&str],
+        data: &toml::Value,
+        provided_data: &toml::Value,
+    ) -> Self {
+        let mut report = Self::default();
+
+        for root in roots {
+            for reference in variables::variable_references(registry, root) {
+                let references = report
+                    .references
+                    .entry(reference.template.to_string())
+                    .or_default();
+                if !references.contains(&reference) {
+                    references.push(reference);
+                }
+            }
+        }
+
+        let all_references: Vec<&VariableReference> =
+            report.references.values().flatten().collect();
+
+        report.missing = all_references
+            .iter()
+            .filter(|reference| reference.is_missing(data))
+            .map(|reference| (*reference).clone())
+            .collect();
+
+        report.unused = data::leaf_paths(provided_data)
+            .into_iter()
+            .filter(|leaf| !all_references.iter().any(|r| uses_path(r, leaf)))
+            .map(|leaf| variables::dotted_path(&leaf))
+            .collect();
+
+        report
+    }
+}
+
+/// Check if the reference uses the value at the path.
+/// A rendered value uses all of the values inside of it while the conditions and contexts of the
+/// blocks only use the value itself.
+fn uses_path(
+    reference: &VariableReference,
+    path: &[String],
+) -> bool {
+    let matches_prefix = reference.path.len() <= path.len()
+        && reference.path.iter().zip(path.iter()).all(|(a, b)| {
+            a == b || a == data::ANY_ITEM || (b == data::ANY_ITEM && a.parse::<usize>().is_ok())
+        });
+
+    match reference.kind {
+        ReferenceKind::Value => matches_prefix,
+        ReferenceKind::Condition | ReferenceKind::Context => {
+            matches_prefix && reference.path.len() == path.len()
+        }
+    }
+}
+
+fn format_position(reference: &VariableReference) -> String {
+    match reference.position {
+        Some((line, column)) => format!(" (line {}, column {})", line, column),
+        None => String::new(),
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        for (name, references) in self.references.iter() {
+            writeln!(f, "Template {:?} references:", name)?;
+
+            for reference in references {
+                writeln!(
+                    f,
+                    "    {}{}",
+                    reference.dotted_path(),
+                    format_position(reference)
+                )?;
+            }
+
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Data keys not referenced by any template:")?;
+        for path in self.unused.iter() {
+            writeln!(f, "    {}", path)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Referenced variables not provided by the data:")?;
+        for reference in self.missing.iter() {
+            writeln!(
+                f,
+                "    {} in template {:?}{}",
+                reference.dotted_path(),
+                reference.template,
+                format_position(reference)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_test() {
+        let mut registry = handlebars::Handlebars::new();
+        registry
+            .register_template_string(
+                "root",
+                "{{#with database}}{{url}}{{/with}}{{#each services}}{{name}}{{/each}}{{#if debug}}{{level}}{{/if}}{{ghost}}",
+            )
+            .unwrap();
+
+        let data: toml::Value = toml::from_str(
+            r#"
+            debug = true
+            level = 2
+            unused = "value"
+
+            [database]
+            url = "postgres://"
+            echo = true
+
+            [[services]]
+            name = "a"
+            port = 80
+            "#,
+        )
+        .unwrap();
+
+        let report = Report::new(&registry, &["root"], &data, &data);
+        assert_eq!(
+            report.unused,
+            vec!["database.echo", "services[].port", "unused"]
+        );
+
+        let missing: Vec<String> = report.missing.iter().map(|r| r.dotted_path()).collect();
+        assert_eq!(missing, vec!["ghost"]);
+    }
+}
@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use jsonschema::JSONSchema;
+
+use crate::data;
+
+/// A value in the data that does not satisfy the schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The JSON pointer of the value (e.g., `/database/port`).
+    pub pointer: String,
+
+    /// The path segments of the value from the root of the data.
+    pub path: Vec<String>,
+
+    pub message: String,
+
+    /// The data source that supplied the value, if known.
+    pub source: Option<String>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let pointer = match self.pointer.is_empty() {
+            true => "/",
+            false => &self.pointer,
+        };
+
+        write!(f, "{}: {}", pointer, self.message)?;
+
+        if let Some(source) = &self.source {
+            write!(f, " (from {})", source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A JSON Schema for validating the data.
+pub struct Schema {
+    schema: serde_json::Value,
+    compiled: JSONSchema,
+}
+
+impl Schema {
+    pub fn new(schema: serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let compiled =
+            JSONSchema::compile(&schema).map_err(|e| format!("The schema is not valid: {}", e))?;
+
+        Ok(Self { schema, compiled })
+    }
+
+    /// Read the schema from a file.
+    /// The schema can be written in JSON, YAML, or TOML detected from the file extension.
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        let extension = path
+            .extension()
+            .map(|v| v.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let schema: serde_json::Value = match extension.as_str() {
+            "yaml" | "yml" => serde_yaml::from_str(&content)?,
+            "toml" => serde_json::to_value(toml::from_str::<toml::Value>(&content)?)?,
+            _ => serde_json::from_str(&content)?,
+        };
+
+        Self::new(schema)
+    }
+
+    /// Get all of the values in the data that does not satisfy the schema.
+    pub fn validate(
+        &self,
+        data: &serde_json::Value,
+    ) -> Vec<Violation> {
+        match self.compiled.validate(data) {
+            Ok(_v) => vec![],
+            Err(errors) => errors
+                .map(|e| Violation {
+                    pointer: e.instance_path.to_string(),
+                    path: e.instance_path.clone().into_vec(),
+                    message: e.to_string(),
+                    source: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Fill the missing properties of the data with the defaults from the schema.
+    /// Only the `properties` of the object schemas are followed.
+    pub fn fill_defaults(
+        &self,
+        data: &mut serde_json::Value,
+    ) {
+        fill_defaults(&self.schema, data);
+    }
+}
+
+fn fill_defaults(
+    schema: &serde_json::Value,
+    data: &mut serde_json::Value,
+) {
+    let (properties, object) = match (schema.get("properties"), data.as_object_mut()) {
+        (Some(serde_json::Value::Object(properties)), Some(object)) => (properties, object),
+        _ => return,
+    };
+
+    for (key, property_schema) in properties.iter() {
+        if !object.contains_key(key) {
+            match property_schema.get("default") {
+                Some(default) => {
+                    object.insert(key.to_string(), default.clone());
+                }
+                // Creating the object if any of its properties has a default.
+                None if property_schema.get("properties").is_some() => {
+                    let mut value = serde_json::Value::Object(serde_json::Map::new());
+                    fill_defaults(property_schema, &mut value);
+                    if value.as_object().is_some_and(|v| !v.is_empty()) {
+                        object.insert(key.to_string(), value);
+                    }
+                    continue;
+                }
+                None => continue,
+            }
+        }
+
+        if let Some(value) = object.get_mut(key) {
+            fill_defaults(property_schema, value);
+        }
+    }
+}
+
+/// Validate the merged data with the schema at the given path.
+///
+/// Every violation is reported along with the data source that supplied the value which results
+/// in an error.
+/// If `with_defaults` is set, the missing values are filled with the defaults from the schema
+/// before validating.
+pub fn validate_data(
+    schema_path: &Path,
+    data: toml::Value,
+    with_defaults: bool,
+    data_layers: &[data::DataLayer],
+) -> Result<toml::Value, crate::Error> {
+    let schema_error = |message: String| crate::Error::Schema {
+        path: schema_path.to_path_buf(),
+        message,
+    };
+
+    let schema = Schema::from_path(schema_path).map_err(|e| schema_error(e.to_string()))?;
+    let mut json_data = serde_json::to_value(&data).map_err(|e| schema_error(e.to_string()))?;
+
+    if with_defaults {
+        schema.fill_defaults(&mut json_data);
+    }
+
+    let mut violations = schema.validate(&json_data);
+    if !violations.is_empty() {
+        let loaded_layers = data::load_layers(data_layers);
+        for violation in violations.iter_mut() {
+            violation.source =
+                data::value_source(&loaded_layers, &violation.path).map(|v| v.to_string());
+        }
+    }
+
+    match violations.is_empty() {
+        true => toml::Value::try_from(json_data).map_err(|e| schema_error(e.to_string())),
+        false => Err(crate::Error::SchemaViolations {
+            path: schema_path.to_path_buf(),
+            violations,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn validate_with_defaults_test() {
+        let schema = Schema::new(json!({
+            "type": "object",
+            "required": ["database"],
+            "properties": {
+                "dogs": { "type": "integer" },
+                "database": {
+                    "type": "object",
+                    "required": ["url", "pool"],
+                    "properties": {
+                        "url": { "type": "string" },
+                        "pool": { "type": "integer", "default": 4 }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let mut data = json!({ "dogs": "four", "database": { "url": "postgres://" } });
+        assert_eq!(schema.validate(&data).len(), 2);
+
+        schema.fill_defaults(&mut data);
+        let violations = schema.validate(&data);
+        assert_eq!(data["database"]["pool"], json!(4));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "/dogs");
+        assert_eq!(violations[0].path, vec!["dogs"]);
+    }
+}
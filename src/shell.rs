@@ -0,0 +1,330 @@
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+
+/// A word of the command line after the quotes, the escapes, and the expansions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub value: String,
+
+    /// The column (starting from 1) where the word starts in the line.
+    pub column: usize,
+
+    /// The glob pattern of the word if it has unquoted wildcards (i.e., `*`, `?`, `[`).
+    /// The quoted and escaped parts of the word are escaped in the pattern.
+    pub pattern: Option<String>,
+}
+
+/// The error of parsing the command line at the column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new<S: Into<String>>(
+        column: usize,
+        message: S,
+    ) -> Self {
+        Self {
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Show the error under the line pointing at the column.
+    pub fn show(
+        &self,
+        line: &str,
+    ) -> String {
+        format!(
+            "{}\n{}^ {}",
+            line,
+            " ".repeat(self.column.saturating_sub(1)),
+            self.message
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Split the command line into words similar to a shell.
+///
+/// * Single quotes keep every character as is.
+/// * Double quotes keep every character except for the variables and the escapes of `"`, `\`,
+///   and `$`.
+/// * A backslash outside of the quotes keeps the next character as is (e.g., `\ `).
+/// * A `~` at the start of a word is expanded into the home directory.
+/// * The environment variables (`$NAME`, `${NAME}`) are expanded outside of the single quotes.
+pub fn split(line: &str) -> Result<Vec<Word>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut words = vec![];
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut word = WordBuilder::default();
+
+        if chars[i] == '~'
+            && chars
+                .get(i + 1)
+                .is_none_or(|c| *c == '/' || c.is_whitespace())
+        {
+            let home = home_dir().ok_or_else(|| {
+                ParseError::new(i + 1, "The home directory cannot be found for `~`.")
+            })?;
+            word.push_literal(&home);
+            i += 1;
+        }
+
+        while i < chars.len() && !chars[i].is_whitespace() {
+            match chars[i] {
+                '\'' => {
+                    let end = (i + 1..chars.len())
+                        .find(|j| chars[*j] == '\'')
+                        .ok_or_else(|| ParseError::new(i + 1, "The single quote is not closed."))?;
+                    word.push_literal(&chars[i + 1..end].iter().collect::<String>());
+                    i = end + 1;
+                }
+                '"' => {
+                    let quote = i;
+                    i += 1;
+                    loop {
+                        match chars.get(i) {
+                            None => {
+                                return Err(ParseError::new(
+                                    quote + 1,
+                                    "The double quote is not closed.",
+                                ))
+                            }
+                            Some('"') => {
+                                i += 1;
+                                break;
+                            }
+                            Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\' | '$')) => {
+                                word.push_literal(&chars[i + 1].to_string());
+                                i += 2;
+                            }
+                            Some('$') => {
+                                let (value, next) = expand_variable(&chars, i)?;
+                                word.push_literal(&value);
+                                i = next;
+                            }
+                            Some(c) => {
+                                word.push_literal(&c.to_string());
+                                i += 1;
+                            }
+                        }
+                    }
+                }
+                '\\' => {
+                    let escaped = chars.get(i + 1).ok_or_else(|| {
+                        ParseError::new(i + 1, "There's nothing to escape at the end of the line.")
+                    })?;
+                    word.push_literal(&escaped.to_string());
+                    i += 2;
+                }
+                '$' => {
+                    let (value, next) = expand_variable(&chars, i)?;
+                    word.push_literal(&value);
+                    i = next;
+                }
+                c => {
+                    word.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        words.push(word.build(start + 1));
+    }
+
+    Ok(words)
+}
+
+/// Get the paths of the words where the words with wildcards are expanded into the matching
+/// paths in alphabetical order.
+pub fn expand_paths(words: &[Word]) -> Result<Vec<PathBuf>, ParseError> {
+    let mut paths = vec![];
+
+    for word in words.iter() {
+        let pattern = match &word.pattern {
+            Some(v) => v,
+            None => {
+                paths.push(PathBuf::from(&word.value));
+                continue;
+            }
+        };
+
+        let matches: Vec<PathBuf> = glob::glob(pattern)
+            .map_err(|e| {
+                ParseError::new(
+                    word.column,
+                    format!("The glob pattern is not valid: {}", e.msg),
+                )
+            })?
+            .filter_map(|v| v.ok())
+            .collect();
+        if matches.is_empty() {
+            return Err(ParseError::new(
+                word.column,
+                format!("There's no path matching the pattern {:?}.", word.value),
+            ));
+        }
+
+        paths.extend(matches);
+    }
+
+    Ok(paths)
+}
+
+#[derive(Debug, Default)]
+struct WordBuilder {
+    value: String,
+    pattern: String,
+    has_wildcard: bool,
+}
+
+impl WordBuilder {
+    /// Add an unquoted character which may be a wildcard.
+    fn push(
+        &mut self,
+        c: char,
+    ) {
+        self.has_wildcard |= matches!(c, '*' | '?' | '[');
+        self.value.push(c);
+        self.pattern.push(c);
+    }
+
+    /// Add the text that is never a wildcard (e.g., quoted, escaped, expanded).
+    fn push_literal(
+        &mut self,
+        text: &str,
+    ) {
+        self.value.push_str(text);
+        self.pattern.push_str(&glob::Pattern::escape(text));
+    }
+
+    fn build(
+        self,
+        column: usize,
+    ) -> Word {
+        Word {
+            value: self.value,
+            column,
+            pattern: match self.has_wildcard {
+                true => Some(self.pattern),
+                false => None,
+            },
+        }
+    }
+}
+
+/// Get the value of the environment variable at the `$` and the index after it.
+/// A `$` not followed by a name is kept as is.
+fn expand_variable(
+    chars: &[char],
+    start: usize,
+) -> Result<(String, usize), ParseError> {
+    let (name, next) = match chars.get(start + 1) {
+        Some('{') => {
+            let end = (start + 2..chars.len())
+                .find(|j| chars[*j] == '}')
+                .ok_or_else(|| {
+                    ParseError::new(start + 1, "The variable name is not closed with `}`.")
+                })?;
+            (chars[start + 2..end].iter().collect::<String>(), end + 1)
+        }
+        _ => {
+            let end = (start + 1..chars.len())
+                .find(|j| !(chars[*j].is_ascii_alphanumeric() || chars[*j] == '_'))
+                .unwrap_or(chars.len());
+            (chars[start + 1..end].iter().collect::<String>(), end)
+        }
+    };
+
+    if name.is_empty() {
+        return match next == start + 1 {
+            true => Ok(("$".to_string(), next)),
+            false => Err(ParseError::new(start + 1, "The variable name is empty.")),
+        };
+    }
+
+    let value = env::var(&name).map_err(|_| {
+        ParseError::new(
+            start + 1,
+            format!("The environment variable {:?} is not set.", name),
+        )
+    })?;
+
+    Ok((value, next))
+}
+
+fn home_dir() -> Option<String> {
+    env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(line: &str) -> Vec<String> {
+        split(line).unwrap().into_iter().map(|v| v.value).collect()
+    }
+
+    #[test]
+    fn split_test() {
+        env::set_var("HANTEMCLI_SHELL_TEST", "dev");
+
+        assert_eq!(
+            values(r#"add data "my configs/$HANTEMCLI_SHELL_TEST.toml" 'a $b' c\ d"#),
+            vec!["add", "data", "my configs/dev.toml", "a $b", "c d"]
+        );
+        assert_eq!(
+            values("cd ${HANTEMCLI_SHELL_TEST}/x $"),
+            vec!["cd", "dev/x", "$"]
+        );
+        assert_eq!(
+            values("cd ~/projects")[1],
+            format!("{}/projects", home_dir().unwrap())
+        );
+
+        assert_eq!(split(r#"add data "my configs"#).unwrap_err().column, 10);
+        assert_eq!(split("cd $HANTEMCLI_SHELL_GHOST").unwrap_err().column, 4);
+        assert_eq!(split(r"cd trailing\").unwrap_err().column, 12);
+    }
+
+    #[test]
+    fn expand_paths_test() {
+        let words = split("tests/d*.toml 'tests/[literal].toml'").unwrap();
+        assert_eq!(words[1].pattern, None);
+        assert_eq!(
+            expand_paths(&words).unwrap(),
+            vec![
+                PathBuf::from("tests/default.toml"),
+                PathBuf::from("tests/dev.toml"),
+                PathBuf::from("tests/[literal].toml"),
+            ]
+        );
+
+        assert!(expand_paths(&split("tests/*.ghost").unwrap()).is_err());
+    }
+}
@@ -1,87 +1,632 @@
 use std::error::Error;
+use std::fmt;
+use std::fs;
 use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
 
-use handlebars;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::Deserialize;
 
-pub fn register_from_path(
-    template_registry: &mut handlebars::Handlebars,
-    paths: Vec<PathBuf>,
-    extension: &str,
-) -> Result<Vec<PathBuf>, Box<dyn Error>> {
-    // Sanitizing the path naively.
-    let extension = match extension.starts_with(".") {
-        true => extension.to_string(),
-        false => format!(".{}", extension),
+/// How the values are escaped when rendered with the double-stash expression (i.e., `{{value}}`).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeMode {
+    Html,
+    None,
+}
+
+impl FromStr for EscapeMode {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "html" => Ok(Self::Html),
+            "none" => Ok(Self::None),
+            _ => Err(format!("No such escape mode as {:?}", string)),
+        }
+    }
+}
+
+/// How the name of a template in the registry is derived from its file path.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NameStyle {
+    /// The path relative from the given directory (or the parent directory of the given file)
+    /// without the file extension.
+    #[default]
+    Relative,
+
+    /// The file name without the file extension.
+    Stem,
+
+    /// The path relative from the current directory without the file extension.
+    Path,
+
+    /// The path relative from the given directory with the file extension.
+    WithExtension,
+}
+
+impl FromStr for NameStyle {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "relative" => Ok(Self::Relative),
+            "stem" => Ok(Self::Stem),
+            "path" => Ok(Self::Path),
+            "with-extension" => Ok(Self::WithExtension),
+            _ => Err(format!("No such name style as {:?}", string)),
+        }
+    }
+}
+
+/// The metadata of a template given by its front matter.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    /// The path of the output file if the output is not given.
+    pub output: Option<PathBuf>,
+
+    /// The default data with the lowest precedence than the data files.
+    pub data: Option<serde_json::Value>,
+
+    /// Whether the template is only used as a partial and should not be rendered as the root.
+    pub partial: bool,
+
+    /// The keys (in the dotted notation) that are required to be in the data.
+    pub required: Vec<String>,
+
+    pub escape: Option<EscapeMode>,
+
+    /// The custom metadata of the template (e.g., a title).
+    pub meta: serde_json::Map<String, serde_json::Value>,
+
+    /// The values in the front matter available as `@meta` in the template.
+    /// The values of the custom metadata are at the top level along with the other keys.
+    #[serde(skip)]
+    pub values: serde_json::Value,
+}
+
+/// Split the front matter from the template source.
+///
+/// The front matter is an optional block at the start of the template either in YAML delimited
+/// with `---` or in TOML delimited with `+++`.
+/// The front matter in the resulting template is replaced with a comment spanning the same
+/// number of lines to keep the line numbers.
+/// A block that is not a mapping of the known keys with valid values (e.g., a YAML template
+/// starting with `---`) is kept as a part of the template.
+pub fn split_front_matter(source: &str) -> (Option<FrontMatter>, String) {
+    let mut lines = source.split_inclusive('\n');
+    let delimiter = match lines.next().map(|l| l.trim_end()) {
+        Some(v) if v == "---" || v == "+++" => v,
+        _ => return (None, source.to_string()),
     };
 
-    let mut registered_files = vec![];
+    let mut content = String::new();
+    let mut line_count = 1;
+    let mut is_closed = false;
+    for line in lines.by_ref() {
+        line_count += 1;
+        if line.trim_end() == delimiter {
+            is_closed = true;
+            break;
+        }
+
+        content.push_str(line);
+    }
+
+    let front_matter = match is_closed {
+        true => parse_front_matter(delimiter, &content),
+        false => None,
+    };
+    match front_matter {
+        Some(front_matter) => {
+            let template = format!(
+                "{{{{!{}}}}}{}",
+                "\n".repeat(line_count),
+                lines.collect::<String>()
+            );
+            (Some(front_matter), template)
+        }
+        None => (None, source.to_string()),
+    }
+}
+
+/// Parse the content of the front matter if it is a mapping of the known keys.
+fn parse_front_matter(
+    delimiter: &str,
+    content: &str,
+) -> Option<FrontMatter> {
+    let values: serde_json::Value = match delimiter {
+        "+++" => serde_json::to_value(toml::from_str::<toml::Value>(content).ok()?).ok()?,
+        _ if content.trim().is_empty() => serde_json::Value::Object(serde_json::Map::new()),
+        _ => serde_yaml::from_str(content).ok()?,
+    };
+
+    // Other values (e.g., a list) would be deserialized into the fields by their position.
+    let mut values = match values {
+        serde_json::Value::Object(v) => v,
+        _ => return None,
+    };
+
+    let mut front_matter: FrontMatter =
+        serde_json::from_value(serde_json::Value::Object(values.clone())).ok()?;
+    values.remove("meta");
+    for (key, value) in front_matter.meta.iter() {
+        values
+            .entry(key.to_string())
+            .or_insert_with(|| value.clone());
+    }
+    front_matter.values = serde_json::Value::Object(values);
+
+    Some(front_matter)
+}
+
+/// A template registered into the template registry.
+#[derive(Debug, Clone)]
+pub struct RegisteredTemplate {
+    pub name: String,
+    pub path: PathBuf,
+    pub front_matter: FrontMatter,
+}
+
+/// A template file found from the given paths.
+#[derive(Debug, Clone)]
+pub struct TemplateFile {
+    pub path: PathBuf,
+
+    /// The directory where the name of the template is relative to.
+    pub base_dir: PathBuf,
+
+    /// The prefix of the name of the template given with the `NAMESPACE=PATH` argument.
+    pub namespace: Option<String>,
+}
+
+impl TemplateFile {
+    /// Get the name of the template in the registry with the name style.
+    /// The namespace, if any, is prepended to the name (e.g., `layouts/base`).
+    pub fn name(
+        &self,
+        name_style: NameStyle,
+    ) -> Result<String, String> {
+        let name = match name_style {
+            NameStyle::Relative => template_name(&self.path, &self.base_dir)?,
+            NameStyle::Stem => self
+                .path
+                .file_stem()
+                .map(|v| v.to_string_lossy().to_string())
+                .ok_or_else(|| format!("{:?} have an error getting the file name.", self.path))?,
+            NameStyle::Path => template_name(&self.path, Path::new(""))?,
+            NameStyle::WithExtension => relative_path_from(
+                naively_normalize_path(&self.path),
+                naively_normalize_path(&self.base_dir),
+            )
+            .map(|v| v.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                format!(
+                    "{:?} has an error getting the relative path of the template.",
+                    self.path
+                )
+            })?,
+        };
+
+        match &self.namespace {
+            Some(namespace) => Ok(format!("{}/{}", namespace, name)),
+            None => Ok(name),
+        }
+    }
+}
+
+/// Split the namespace from the template argument with the `NAMESPACE=PATH` syntax.
+/// An existing path is not split even if it has the `=` character.
+pub fn split_namespace(path: PathBuf) -> (Option<String>, PathBuf) {
+    if path.exists() {
+        return (None, path);
+    }
+
+    match path.to_str().and_then(|v| v.split_once('=')) {
+        Some((namespace, template)) if !namespace.is_empty() => (
+            Some(namespace.trim_matches('/').to_string()),
+            PathBuf::from(template),
+        ),
+        _ => (None, path),
+    }
+}
+
+/// The name of the ignore file in the template directories similar to `.gitignore`.
+pub static IGNORE_FILE_NAME: &str = ".hantemignore";
+
+/// The rules for selecting the template files.
+#[derive(Debug, Clone)]
+pub struct TemplateFilter {
+    /// The file extensions of the template files with the leading dot (e.g., `.hbs`).
+    pub extensions: Vec<String>,
+
+    /// The files in the directories must match one of the patterns if there's any.
+    pub include: GlobSet,
+
+    /// The files in the directories must not match any of the patterns.
+    pub exclude: GlobSet,
+
+    /// Whether the hidden files and directories are searched.
+    pub hidden: bool,
+}
+
+impl Default for TemplateFilter {
+    fn default() -> Self {
+        Self {
+            extensions: vec![".hbs".to_string()],
+            include: GlobSet::empty(),
+            exclude: GlobSet::empty(),
+            hidden: false,
+        }
+    }
+}
+
+impl TemplateFilter {
+    pub fn new<S: AsRef<str>>(
+        extensions: &[S],
+        include: &[S],
+        exclude: &[S],
+        hidden: bool,
+    ) -> Result<Self, globset::Error> {
+        Ok(Self {
+            // Sanitizing the extensions naively.
+            extensions: extensions
+                .iter()
+                .map(|extension| match extension.as_ref().starts_with('.') {
+                    true => extension.as_ref().to_string(),
+                    false => format!(".{}", extension.as_ref()),
+                })
+                .collect(),
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+            hidden,
+        })
+    }
+
+    /// Check if the path has one of the file extensions.
+    pub fn has_extension(
+        &self,
+        path: &Path,
+    ) -> bool {
+        self.extensions
+            .iter()
+            .any(|extension| has_file_extension(path, extension))
+    }
+
+    /// Check if the path relative from the searched directory matches the glob patterns.
+    pub fn matches_globs(
+        &self,
+        relative_path: &Path,
+    ) -> bool {
+        (self.include.is_empty() || self.include.is_match(relative_path))
+            && !self.exclude.is_match(relative_path)
+    }
+}
+
+fn build_glob_set<S: AsRef<str>>(patterns: &[S]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern.as_ref())?);
+    }
+
+    builder.build()
+}
+
+/// Find the template files with the file extensions from the given paths.
+///
+/// If the path is a directory, it will search for the template files in the directory and its
+/// subdirectories with the directory as the base directory.
+/// The search follows the include and exclude patterns along with the rules from the
+/// `.hantemignore` and `.gitignore` files while skipping the hidden files by default.
+/// Otherwise, the parent directory of the file is the base directory and only its file extension
+/// is checked.
+/// The paths can be given with a namespace (i.e., `NAMESPACE=PATH`).
+/// The errors while searching the directories (e.g., an unreadable directory) are returned as
+/// failures.
+pub fn find_template_files(
+    paths: Vec<PathBuf>,
+    filter: &TemplateFilter,
+) -> (Vec<TemplateFile>, Vec<RegistrationFailure>) {
+    let mut template_files = vec![];
+    let mut failures = vec![];
 
     for template in paths {
+        let (namespace, template) = split_namespace(template);
+
         if template.is_dir() {
-            let walker = walkdir::WalkDir::new(&template).min_depth(1).into_iter();
-            for entry in walker
-                .filter_map(|e| e.ok())
-                .filter(|e| e.path().is_file() && has_file_extension(e.path(), &extension))
-            {
-                if register_file_to_template_registry(template_registry, &entry.path(), &template) {
-                    registered_files.push(entry.path().to_path_buf());
+            let walker = WalkBuilder::new(&template)
+                .hidden(!filter.hidden)
+                .parents(false)
+                .git_global(false)
+                .require_git(false)
+                .add_custom_ignore_filename(IGNORE_FILE_NAME)
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .build();
+            for entry in walker.filter_map(|e| match e {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    failures.push(RegistrationFailure {
+                        path: walk_error_path(&e).unwrap_or(&template).to_path_buf(),
+                        line: None,
+                        column: None,
+                        message: e.to_string(),
+                    });
+                    None
+                }
+            }) {
+                let path = entry.path();
+                let relative_path = path.strip_prefix(&template).unwrap_or(path);
+                if path.is_file()
+                    && filter.has_extension(path)
+                    && filter.matches_globs(relative_path)
+                {
+                    template_files.push(TemplateFile {
+                        path: path.to_path_buf(),
+                        base_dir: template.clone(),
+                        namespace: namespace.clone(),
+                    });
                 }
             }
         } else {
-            if !has_file_extension(&template, &extension) {
+            if !filter.has_extension(&template) {
                 continue;
             }
 
-            if register_file_to_template_registry(
-                template_registry,
-                &template,
-                &template.parent().unwrap_or(Path::new("./")),
-            ) {
-                registered_files.push(template.to_path_buf());
-            }
+            let base_dir = template
+                .parent()
+                .unwrap_or_else(|| Path::new("./"))
+                .to_path_buf();
+            template_files.push(TemplateFile {
+                path: template,
+                base_dir,
+                namespace,
+            });
         }
     }
 
-    Ok(registered_files)
+    (template_files, failures)
 }
 
-// A closure to easily register a path into the template registry.
-// It will return a boolean indicating the success of the registration.
-pub fn register_file_to_template_registry(
+/// Get the path where the error occurred while searching the template directory.
+fn walk_error_path(error: &ignore::Error) -> Option<&Path> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        ignore::Error::Partial(errors) => errors.iter().find_map(walk_error_path),
+        _ => None,
+    }
+}
+
+/// A template file that failed to be registered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationFailure {
+    pub path: PathBuf,
+
+    /// The position of the parsing error in the template file, if any.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+
+    pub message: String,
+}
+
+impl fmt::Display for RegistrationFailure {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(f, "{}:", self.path.display())?;
+
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+        }
+
+        if let Some(column) = self.column {
+            write!(f, "{}:", column)?;
+        }
+
+        write!(f, " {}", self.message)
+    }
+}
+
+/// The error of registering all of the failed template files at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationError {
+    pub failures: Vec<RegistrationFailure>,
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "{} template file(s) failed to be registered:",
+            self.failures.len()
+        )?;
+
+        for failure in self.failures.iter() {
+            write!(f, "\n{}", failure)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for RegistrationError {}
+
+/// The result of registering the template files from the given paths.
+#[derive(Debug, Default)]
+pub struct Registration {
+    pub templates: Vec<RegisteredTemplate>,
+    pub failures: Vec<RegistrationFailure>,
+}
+
+impl Registration {
+    /// Give an error if some of the template files failed to be registered.
+    ///
+    /// If `keep_going` is set, the registration is returned as is with the failures to be
+    /// reported by the caller.
+    pub fn into_result(
+        self,
+        keep_going: bool,
+    ) -> Result<Self, RegistrationError> {
+        match self.failures.is_empty() || keep_going {
+            true => Ok(self),
+            false => Err(RegistrationError {
+                failures: self.failures,
+            }),
+        }
+    }
+}
+
+/// Register all of the template files found from the given paths.
+/// Every template file is attempted even if some of them failed to be registered.
+///
+/// A template file with the same name as a registered template file (e.g., `a.hbs` and `a.txt`)
+/// is a failure instead of replacing it.
+pub fn register_from_path(
+    template_registry: &mut handlebars::Handlebars,
+    paths: Vec<PathBuf>,
+    filter: &TemplateFilter,
+    name_style: NameStyle,
+) -> Registration {
+    register_more_from_path(template_registry, &[], paths, filter, name_style)
+}
+
+/// Register the template files found from the given paths into a registry with the already
+/// registered templates.
+/// The already registered templates are only replaced by the same file (e.g., to read it again)
+/// while the other files with the same name are failures.
+pub fn register_more_from_path(
     template_registry: &mut handlebars::Handlebars,
+    registered_templates: &[RegisteredTemplate],
+    paths: Vec<PathBuf>,
+    filter: &TemplateFilter,
+    name_style: NameStyle,
+) -> Registration {
+    let (template_files, failures) = find_template_files(paths, filter);
+    let mut registration = Registration {
+        failures,
+        ..Default::default()
+    };
+
+    for template_file in template_files {
+        if let Ok(name) = template_file.name(name_style) {
+            let name_collision = |registered: &RegisteredTemplate| {
+                (naively_normalize_path(&registered.path)
+                    != naively_normalize_path(&template_file.path))
+                .then(|| RegistrationFailure {
+                    path: template_file.path.to_path_buf(),
+                    line: None,
+                    column: None,
+                    message: format!(
+                        "The template name {:?} is already used by {:?}.",
+                        name, registered.path
+                    ),
+                })
+            };
+
+            // The same file given more than once is only registered once.
+            if let Some(registered) = registration.templates.iter().find(|t| t.name == name) {
+                registration.failures.extend(name_collision(registered));
+                continue;
+            }
+
+            if let Some(failure) = registered_templates
+                .iter()
+                .find(|t| t.name == name)
+                .and_then(name_collision)
+            {
+                registration.failures.push(failure);
+                continue;
+            }
+        }
+
+        match register_file_to_template_registry(template_registry, &template_file, name_style) {
+            Ok(v) => registration.templates.push(v),
+            Err(e) => registration.failures.push(e),
+        }
+    }
+
+    registration
+}
+
+/// Get the name of the template in the registry.
+/// The name is the relative path of the template from the base directory without the file
+/// extension.
+pub fn template_name(
     template: &Path,
     base_dir: &Path,
-) -> bool {
-    let normalized_base_dir = naively_normalize_path(&base_dir);
+) -> Result<String, String> {
+    let normalized_base_dir = naively_normalize_path(base_dir);
 
-    let name = match path_without_extension(&template) {
+    let name = match path_without_extension(template) {
         Some(v) => naively_normalize_path(v),
         None => {
-            eprintln!("{:?} have an error getting the file path.", template);
-            return false;
+            return Err(format!(
+                "{:?} have an error getting the file path.",
+                template
+            ))
         }
     };
 
-    let relpath_from_base_dir = match relative_path_from(&name, &normalized_base_dir) {
-        Some(v) => v,
-        None => {
-            eprintln!(
-                "{:?} has an error getting the relative path of the template. How's that possible?",
-                template
-            );
-            return false;
-        }
+    match relative_path_from(&name, &normalized_base_dir) {
+        Some(v) => Ok(v.to_string_lossy().to_string()),
+        None => Err(format!(
+            "{:?} has an error getting the relative path of the template. How's that possible?",
+            template
+        )),
+    }
+}
+
+/// Read the template file and register it with its front matter stripped.
+/// A template without the front matter has the default front matter.
+pub fn register_template_file(
+    template_registry: &mut handlebars::Handlebars,
+    name: &str,
+    template: &Path,
+) -> Result<FrontMatter, Box<dyn Error>> {
+    let source = fs::read_to_string(template)?;
+    let (front_matter, source) = split_front_matter(&source);
+
+    template_registry.register_template_string(name, source)?;
+    Ok(front_matter.unwrap_or_default())
+}
+
+/// Register the template file with the name from the name style.
+pub fn register_file_to_template_registry(
+    template_registry: &mut handlebars::Handlebars,
+    template_file: &TemplateFile,
+    name_style: NameStyle,
+) -> Result<RegisteredTemplate, RegistrationFailure> {
+    let template = &template_file.path;
+    let failure = |line, column, message| RegistrationFailure {
+        path: template.to_path_buf(),
+        line,
+        column,
+        message,
     };
 
-    match template_registry
-        .register_template_file(relpath_from_base_dir.to_str().unwrap(), &template)
-    {
-        Ok(_v) => true,
-        Err(e) => {
-            eprintln!("Template file {:?} has an error.", &template);
-            eprintln!("{}", e);
-            false
-        }
+    let name = template_file
+        .name(name_style)
+        .map_err(|e| failure(None, None, e))?;
+
+    match register_template_file(template_registry, &name, template) {
+        Ok(front_matter) => Ok(RegisteredTemplate {
+            name,
+            path: template.to_path_buf(),
+            front_matter,
+        }),
+        Err(e) => match e.downcast_ref::<handlebars::TemplateError>() {
+            Some(e) => Err(failure(e.line_no, e.column_no, e.reason.to_string())),
+            None => Err(failure(None, None, e.to_string())),
+        },
     }
 }
 
@@ -112,7 +657,7 @@ pub fn has_file_extension(
 ) -> bool {
     entry
         .file_name()
-        .and_then(|file| Some(file.to_str().unwrap().ends_with(ext)))
+        .map(|file| file.to_string_lossy().ends_with(ext))
         .unwrap_or(false)
 }
 
@@ -158,8 +703,8 @@ pub fn relative_path_from<P: AsRef<Path>, Q: AsRef<Path>>(
                 // if the base path has more components
                 (None, _) => common_components.push(Component::ParentDir),
                 (Some(a), Some(b)) if common_components.is_empty() && a == b => (),
-                (Some(a), Some(b)) if b == Component::CurDir => common_components.push(a),
-                (Some(_), Some(b)) if b == Component::ParentDir => return None,
+                (Some(a), Some(Component::CurDir)) => common_components.push(a),
+                (Some(_), Some(Component::ParentDir)) => return None,
                 (Some(a), Some(_)) => {
                     common_components.push(Component::ParentDir);
                     for _ in base_path_components {
@@ -176,13 +721,6 @@ pub fn relative_path_from<P: AsRef<Path>, Q: AsRef<Path>>(
     }
 }
 
-fn is_parent_dir(component: Component) -> bool {
-    match component {
-        Component::ParentDir => true,
-        _ => false,
-    }
-}
-
 /// Normalize the given path.
 /// Unlike the standard library `std::fs::canonicalize` function, it does not need the file to be in the filesystem.
 ///
@@ -198,18 +736,12 @@ pub fn naively_normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
     for component in path.components() {
         match &component {
             Component::CurDir => continue,
-            // The condition below can be safe to execute.
-            // It will immediately continue to the if block if one of them is true which is why
-            // the ordering of the conditions is important.
-            // If the vector is empty, it will never reach the second condition.
-            // That said, there has to be a better way than this.
-            Component::ParentDir => match normalized_components.is_empty()
-                || is_parent_dir(normalized_components[normalized_components.len() - 1])
-            {
-                true => normalized_components.push(component),
-                false => {
+            Component::ParentDir => match normalized_components.last() {
+                None | Some(Component::ParentDir) => normalized_components.push(component),
+                // The parent of the root directory is itself.
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => continue,
+                Some(_) => {
                     normalized_components.pop();
-                    ()
                 }
             },
             _ => normalized_components.push(component),
@@ -218,3 +750,228 @@ pub fn naively_normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
 
     normalized_components.iter().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_front_matter_test() {
+        let (front_matter, template) =
+            split_front_matter("---\noutput: out.conf\npartial: true\n---\nHello {{name}}\n");
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.output, Some(PathBuf::from("out.conf")));
+        assert!(front_matter.partial);
+        assert_eq!(front_matter.values["output"], "out.conf");
+        assert_eq!(template, "{{!\n\n\n\n}}Hello {{name}}\n");
+
+        let (front_matter, _) =
+            split_front_matter("+++\nescape = \"none\"\nrequired = [\"a.b\"]\n+++\n");
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.escape, Some(EscapeMode::None));
+        assert_eq!(front_matter.required, vec!["a.b"]);
+
+        let (front_matter, template) = split_front_matter("---\nnot closed");
+        assert_eq!(front_matter, None);
+        assert_eq!(template, "---\nnot closed");
+
+        // The custom metadata is at the top level of `@meta`.
+        let (front_matter, _) =
+            split_front_matter("---\noutput: out.conf\nmeta:\n  title: Hello\n---\n");
+        assert_eq!(
+            front_matter.unwrap().values,
+            serde_json::json!({ "output": "out.conf", "title": "Hello" })
+        );
+    }
+
+    #[test]
+    fn split_front_matter_yaml_template_test() {
+        // A YAML template with a block that cannot be parsed.
+        let source = "---\nname: {{name}}\n---\nother: 1";
+        assert_eq!(split_front_matter(source), (None, source.to_string()));
+
+        // A block that is not a mapping.
+        let source = "---\n- a\n- b\n---\nbody";
+        assert_eq!(split_front_matter(source), (None, source.to_string()));
+
+        // A mapping with the keys not known in the front matter.
+        let source = "---\nname: world\n---\nother: 1";
+        assert_eq!(split_front_matter(source), (None, source.to_string()));
+
+        let mut registry = handlebars::Handlebars::new();
+        let directory =
+            std::env::temp_dir().join(format!("hantemcli-yaml-template-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("list.yaml"), "---\n- a\n- b\n---\nbody").unwrap();
+        let front_matter =
+            register_template_file(&mut registry, "list", &directory.join("list.yaml")).unwrap();
+        assert_eq!(front_matter.output, None);
+        assert_eq!(
+            registry.render("list", &()).unwrap(),
+            "---\n- a\n- b\n---\nbody"
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn template_file_name_test() {
+        let template_file = TemplateFile {
+            path: PathBuf::from("./tests/layouts/base.tex.hbs"),
+            base_dir: PathBuf::from("tests"),
+            namespace: None,
+        };
+        assert_eq!(
+            template_file.name(NameStyle::Relative).unwrap(),
+            "layouts/base.tex"
+        );
+        assert_eq!(template_file.name(NameStyle::Stem).unwrap(), "base.tex");
+        assert_eq!(
+            template_file.name(NameStyle::Path).unwrap(),
+            "tests/layouts/base.tex"
+        );
+        assert_eq!(
+            template_file.name(NameStyle::WithExtension).unwrap(),
+            "layouts/base.tex.hbs"
+        );
+
+        let (namespace, path) = split_namespace(PathBuf::from("theme=tests/"));
+        let template_file = TemplateFile {
+            path: path.join("base.hbs"),
+            base_dir: path,
+            namespace,
+        };
+        assert_eq!(
+            template_file.name(NameStyle::Relative).unwrap(),
+            "theme/base"
+        );
+    }
+
+    #[test]
+    fn find_template_files_test() {
+        let directory = std::env::temp_dir().join(format!("hantemcli-find-{}", std::process::id()));
+        for file in &[
+            "page.hbs",
+            "page.hbs~",
+            "notes.md",
+            "drafts/draft.hbs",
+            "ignored/skipped.hbs",
+            "vendor/lib.hbs",
+            ".git/config.hbs",
+            "report.tex",
+        ] {
+            let path = directory.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(directory.join(".gitignore"), "vendor/\n").unwrap();
+        fs::write(directory.join(IGNORE_FILE_NAME), "ignored/\n").unwrap();
+
+        let filter = TemplateFilter::new(&["hbs", ".tex"], &[], &["drafts/**"], false).unwrap();
+        let (template_files, failures) = find_template_files(vec![directory.clone()], &filter);
+        assert!(failures.is_empty());
+        let paths: Vec<PathBuf> = template_files
+            .into_iter()
+            .map(|t| t.path.strip_prefix(&directory).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("page.hbs"), PathBuf::from("report.tex")]
+        );
+
+        // The errors while searching are failures instead of being printed.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let locked = directory.join("locked");
+            fs::create_dir_all(&locked).unwrap();
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+            // The permissions are not enforced for the superuser.
+            if fs::read_dir(&locked).is_err() {
+                let (_template_files, failures) =
+                    find_template_files(vec![directory.clone()], &filter);
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].path, locked);
+            }
+
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn naively_normalize_path_test() {
+        assert_eq!(
+            naively_normalize_path("./a/../../b/./c"),
+            PathBuf::from("../b/c")
+        );
+        assert_eq!(naively_normalize_path("/a/../.."), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn register_from_path_test() {
+        let directory =
+            std::env::temp_dir().join(format!("hantemcli-register-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("broken.hbs"), "Hello\n{{#if}}").unwrap();
+        fs::write(directory.join("valid.hbs"), "Hello {{name}}").unwrap();
+
+        let mut registry = handlebars::Handlebars::new();
+        let registration = register_from_path(
+            &mut registry,
+            vec![directory.clone()],
+            &TemplateFilter::default(),
+            NameStyle::Relative,
+        );
+        assert_eq!(registration.templates.len(), 1);
+        assert_eq!(registration.failures.len(), 1);
+        assert_eq!(registration.failures[0].path, directory.join("broken.hbs"));
+        assert_eq!(registration.failures[0].line, Some(2));
+
+        let error = register_from_path(
+            &mut registry,
+            vec![directory.clone()],
+            &TemplateFilter::default(),
+            NameStyle::Relative,
+        )
+        .into_result(false)
+        .unwrap_err();
+        assert_eq!(error.failures.len(), 1);
+
+        fs::write(directory.join("valid.txt"), "Hi {{name}}").unwrap();
+        let registration = register_from_path(
+            &mut handlebars::Handlebars::new(),
+            vec![directory.clone(), directory.join("valid.hbs")],
+            &TemplateFilter::new(&["hbs", "txt"], &[], &[], false).unwrap(),
+            NameStyle::Relative,
+        );
+        let failures: Vec<&RegistrationFailure> = registration
+            .failures
+            .iter()
+            .filter(|failure| failure.path == directory.join("valid.txt"))
+            .collect();
+        assert_eq!(registration.templates.len(), 1);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0]
+            .message
+            .contains(&format!("{:?}", directory.join("valid.hbs"))));
+
+        // The already registered templates are only replaced by the same file.
+        let registration = register_more_from_path(
+            &mut registry,
+            &registration.templates,
+            vec![directory.join("valid.txt"), directory.join("valid.hbs")],
+            &TemplateFilter::new(&["hbs", "txt"], &[], &[], false).unwrap(),
+            NameStyle::Relative,
+        );
+        assert_eq!(registration.templates.len(), 1);
+        assert_eq!(registration.templates[0].path, directory.join("valid.hbs"));
+        assert_eq!(registration.failures.len(), 1);
+        assert_eq!(registration.failures[0].path, directory.join("valid.txt"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
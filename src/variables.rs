@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+
+use handlebars::template::{BlockParam, HelperTemplate, Parameter, Template, TemplateElement};
+
+use crate::data;
+use crate::graph;
+
+/// How a variable is used by the template.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceKind {
+    /// The value is rendered or given to a helper.
+    Value,
+
+    /// The value is the condition of an `if` or `unless` block where a missing value is expected.
+    Condition,
+
+    /// The value is the context of a block (e.g., `each` and `with` blocks).
+    Context,
+}
+
+/// A variable path referenced by a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableReference {
+    /// The name of the template where the reference is found.
+    pub template: String,
+
+    /// The path segments from the root of the data.
+    /// The items of an array or table (e.g., in an `each` block) are referred with the
+    /// `data::ANY_ITEM` segment.
+    pub path: Vec<String>,
+
+    /// The line and column of the reference in the template if the source map is available.
+    pub position: Option<(usize, usize)>,
+
+    pub kind: ReferenceKind,
+
+    /// The paths of the `if` blocks enclosing the reference.
+    /// The reference is only rendered if all of them are present.
+    pub guards: Vec<Vec<String>>,
+}
+
+impl VariableReference {
+    /// The path in the dotted notation (e.g., `services[].name`).
+    pub fn dotted_path(&self) -> String {
+        dotted_path(&self.path)
+    }
+
+    /// Whether the referenced value is missing from the data when the template is rendered.
+    /// Conditions and references inside of an `if` block with a missing condition are never
+    /// missing.
+    pub fn is_missing(
+        &self,
+        data: &toml::Value,
+    ) -> bool {
+        let is_guarded = self
+            .guards
+            .iter()
+            .any(|guard| !data::contains_path(data, guard));
+
+        self.kind != ReferenceKind::Condition
+            && !is_guarded
+            && !data::contains_path(data, &self.path)
+    }
+}
+
+/// Join the path segments in the dotted notation (e.g., `services[].name`).
+pub fn dotted_path<S: AsRef<str>>(path: &[S]) -> String {
+    let mut dotted = String::new();
+    for segment in path {
+        let segment = segment.as_ref();
+        if !dotted.is_empty() && segment != data::ANY_ITEM {
+            dotted.push('.');
+        }
+
+        dotted.push_str(segment);
+    }
+
+    dotted
+}
+
+/// The data context of a block in the template.
+#[derive(Debug, Clone, PartialEq)]
+enum Scope {
+    /// The context is a value at the path from the root of the data.
+    Known(Vec<String>),
+
+    /// The context cannot be known without rendering (e.g., a context given by a custom helper).
+    Unknown,
+}
+
+/// The state of the block being walked.
+#[derive(Debug, Clone)]
+struct Context {
+    /// The contexts of the enclosing blocks with the current context as the last one.
+    scopes: Vec<Scope>,
+
+    guards: Vec<Vec<String>>,
+
+    /// The block parameters (e.g., `{{#each items as |item|}}`) in the current block.
+    block_params: HashMap<String, Scope>,
+}
+
+impl Context {
+    fn with_scope(
+        &self,
+        scope: Scope,
+    ) -> Self {
+        let mut context = self.clone();
+        context.scopes.push(scope);
+        context
+    }
+
+    /// Resolve the path parameter into the path from the root of the data.
+    fn resolve(
+        &self,
+        parameter: &Parameter,
+    ) -> Option<Vec<String>> {
+        let raw = match parameter {
+            Parameter::Path(_) => parameter.as_name()?,
+            _ => return None,
+        };
+
+        let (level, mut segments) = parse_path(raw)?;
+        let scope = match level {
+            PathLevel::Root => Scope::Known(vec![]),
+            PathLevel::Parent(0) if !segments.is_empty() => {
+                match self.block_params.get(&segments[0]) {
+                    Some(scope) => {
+                        segments.remove(0);
+                        scope.clone()
+                    }
+                    None => self.scopes.last()?.clone(),
+                }
+            }
+            PathLevel::Parent(level) => self.scopes.iter().rev().nth(level)?.clone(),
+        };
+
+        match scope {
+            Scope::Known(mut path) => {
+                path.extend(segments);
+                Some(path)
+            }
+            Scope::Unknown => None,
+        }
+    }
+}
+
+/// Collect the variable paths referenced by the template and the partials it includes.
+///
+/// Only the paths that can be resolved statically are collected.
+/// The context changes from `each` and `with` blocks (along with their block parameters) and
+/// partials are followed.
+/// Paths inside blocks with an unknown context and local variables (e.g., `@index`) are skipped.
+pub fn variable_references(
+    registry: &handlebars::Handlebars,
+    root: &str,
+) -> Vec<VariableReference> {
+    let mut walker = Walker {
+        registry,
+        references: vec![],
+        partial_stack: vec![],
+    };
+
+    let context = Context {
+        scopes: vec![Scope::Known(vec![])],
+        guards: vec![],
+        block_params: HashMap::new(),
+    };
+
+    if let Some(template) = registry.get_template(root) {
+        walker.walk_template(root, template, &context);
+    }
+
+    walker.references
+}
+
+struct Walker<'a> {
+    registry: &'a handlebars::Handlebars<'a>,
+    references: Vec<VariableReference>,
+
+    /// The partials currently being walked to avoid infinite recursion with cyclic partials.
+    partial_stack: Vec<String>,
+}
+
+impl<'a> Walker<'a> {
+    fn walk_template(
+        &mut self,
+        name: &str,
+        template: &Template,
+        context: &Context,
+    ) {
+        let mut inline_partials: HashMap<String, &Template> = HashMap::new();
+
+        for (index, element) in template.elements.iter().enumerate() {
+            let position = graph::element_position(template, index);
+
+            match element {
+                TemplateElement::HTMLExpression(parameter) => {
+                    self.add_parameter(name, parameter, position, context, ReferenceKind::Value)
+                }
+                TemplateElement::Expression(helper) => {
+                    if self.is_variable(helper) {
+                        self.add_parameter(
+                            name,
+                            &helper.name,
+                            position,
+                            context,
+                            ReferenceKind::Value,
+                        );
+                    } else {
+                        self.add_helper_parameters(
+                            name,
+                            helper,
+                            position,
+                            context,
+                            ReferenceKind::Value,
+                        );
+                    }
+                }
+                TemplateElement::HelperBlock(helper) => {
+                    self.walk_helper_block(name, helper, position, context)
+                }
+                TemplateElement::DecoratorBlock(decorator)
+                | TemplateElement::DecoratorExpression(decorator)
+                    if graph::static_name(&decorator.name).as_deref() == Some("inline") =>
+                {
+                    if let (
+                        Some(Parameter::Literal(serde_json::Value::String(partial_name))),
+                        Some(t),
+                    ) = (decorator.params.first(), &decorator.template)
+                    {
+                        inline_partials.insert(partial_name.to_string(), t);
+                    }
+                }
+                TemplateElement::PartialExpression(partial)
+                | TemplateElement::PartialBlock(partial) => {
+                    for parameter in partial.params.iter() {
+                        self.add_parameter(
+                            name,
+                            parameter,
+                            position,
+                            context,
+                            ReferenceKind::Context,
+                        );
+                    }
+
+                    for parameter in partial.hash.values() {
+                        self.add_parameter(
+                            name,
+                            parameter,
+                            position,
+                            context,
+                            ReferenceKind::Value,
+                        );
+                    }
+
+                    // A partial given with a context parameter is rendered in that context.
+                    // The hash parameters are added to the context which cannot be followed.
+                    let partial_scope = match (partial.params.first(), partial.hash.is_empty()) {
+                        (None, true) => None,
+                        (Some(parameter), true) => Some(
+                            context
+                                .resolve(parameter)
+                                .map_or(Scope::Unknown, Scope::Known),
+                        ),
+                        (_, false) => Some(Scope::Unknown),
+                    };
+                    let partial_context = match partial_scope {
+                        Some(scope) => context.with_scope(scope),
+                        None => context.clone(),
+                    };
+
+                    if let Some(partial_name) = graph::static_name(&partial.name) {
+                        match inline_partials.get(&partial_name) {
+                            Some(t) => self.walk_template(name, t, &partial_context),
+                            None => self.walk_partial(&partial_name, &partial_context),
+                        }
+                    }
+
+                    if let Some(t) = &partial.template {
+                        self.walk_template(name, t, context);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn walk_partial(
+        &mut self,
+        name: &str,
+        context: &Context,
+    ) {
+        if self.partial_stack.iter().any(|n| n == name) {
+            return;
+        }
+
+        if let Some(template) = self.registry.get_template(name) {
+            self.partial_stack.push(name.to_string());
+            self.walk_template(name, template, context);
+            self.partial_stack.pop();
+        }
+    }
+
+    fn walk_helper_block(
+        &mut self,
+        name: &str,
+        helper: &HelperTemplate,
+        position: Option<(usize, usize)>,
+        context: &Context,
+    ) {
+        let helper_name = graph::static_name(&helper.name).unwrap_or_default();
+        let kind = match helper_name.as_str() {
+            "if" | "unless" => ReferenceKind::Condition,
+            "each" | "with" => ReferenceKind::Context,
+            _ => ReferenceKind::Value,
+        };
+        self.add_helper_parameters(name, helper, position, context, kind);
+
+        let parameter = helper.params.first();
+        let block_context = match helper_name.as_str() {
+            "if" => {
+                let mut block_context = context.clone();
+                if let Some(path) = parameter.and_then(|p| context.resolve(p)) {
+                    block_context.guards.push(path);
+                }
+
+                block_context
+            }
+            "each" | "with" => {
+                let scope = match parameter.and_then(|p| context.resolve(p)) {
+                    Some(mut path) => {
+                        if helper_name == "each" {
+                            path.push(data::ANY_ITEM.to_string());
+                        }
+
+                        Scope::Known(path)
+                    }
+                    None => Scope::Unknown,
+                };
+
+                let mut block_context = context.with_scope(scope.clone());
+                match &helper.block_param {
+                    Some(BlockParam::Single(p)) | Some(BlockParam::Pair((p, _))) => {
+                        if let Some(block_param) = p.as_name() {
+                            block_context
+                                .block_params
+                                .insert(block_param.to_string(), scope);
+                        }
+                    }
+                    None => (),
+                }
+
+                block_context
+            }
+            _ => context.clone(),
+        };
+
+        if let Some(t) = &helper.template {
+            self.walk_template(name, t, &block_context);
+        }
+
+        if let Some(t) = &helper.inverse {
+            self.walk_template(name, t, context);
+        }
+    }
+
+    /// Whether the expression is a variable rather than a helper call.
+    fn is_variable(
+        &self,
+        helper: &HelperTemplate,
+    ) -> bool {
+        let is_helper_name = match &helper.name {
+            Parameter::Path(_) | Parameter::Name(_) => helper
+                .name
+                .as_name()
+                .map(|n| self.registry.get_helper(n).is_some())
+                .unwrap_or(false),
+            _ => true,
+        };
+
+        !helper.block && helper.params.is_empty() && helper.hash.is_empty() && !is_helper_name
+    }
+
+    fn add_helper_parameters(
+        &mut self,
+        name: &str,
+        helper: &HelperTemplate,
+        position: Option<(usize, usize)>,
+        context: &Context,
+        kind: ReferenceKind,
+    ) {
+        for parameter in helper.params.iter().chain(helper.hash.values()) {
+            self.add_parameter(name, parameter, position, context, kind);
+        }
+    }
+
+    fn add_parameter(
+        &mut self,
+        name: &str,
+        parameter: &Parameter,
+        position: Option<(usize, usize)>,
+        context: &Context,
+        kind: ReferenceKind,
+    ) {
+        match parameter {
+            Parameter::Path(_) => {
+                if let Some(path) = context.resolve(parameter) {
+                    self.references.push(VariableReference {
+                        template: name.to_string(),
+                        path,
+                        position,
+                        kind,
+                        guards: context.guards.to_vec(),
+                    });
+                }
+            }
+            Parameter::Subexpression(subexpression) => {
+                let parameters = subexpression
+                    .params()
+                    .into_iter()
+                    .flatten()
+                    .chain(subexpression.hash().into_iter().flat_map(|h| h.values()));
+                for p in parameters {
+                    self.add_parameter(name, p, position, context, ReferenceKind::Value);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The starting point of a path.
+#[derive(Debug, PartialEq)]
+pub enum PathLevel {
+    /// The root of the data (i.e., `@root`).
+    Root,
+
+    /// The number of blocks up from the current context (i.e., the number of `../`).
+    Parent(usize),
+}
+
+/// Parse the Handlebars path expression into its segments.
+///
+/// Segments can be separated with a dot or a slash and can be quoted with brackets
+/// (e.g., `services.[0].name`).
+/// Local variables except `@root` (e.g., `@index`, `@key`) returns `None`.
+pub fn parse_path(raw: &str) -> Option<(PathLevel, Vec<String>)> {
+    let mut segments = vec![];
+    let mut segment = String::new();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    segment.push(c);
+                }
+            }
+            '.' | '/' => {
+                // A parent directory segment (`..`) is pushed as is.
+                if c == '.' && segment.is_empty() && chars.peek() == Some(&'.') {
+                    chars.next();
+                    segment.push_str("..");
+                }
+
+                segments.push(std::mem::take(&mut segment));
+            }
+            _ => segment.push(c),
+        }
+    }
+    segments.push(segment);
+
+    let mut level = PathLevel::Parent(0);
+    let mut path = vec![];
+    for (index, segment) in segments.into_iter().enumerate() {
+        match segment.as_str() {
+            "" | "this" if path.is_empty() => (),
+            ".." if path.is_empty() => {
+                if let PathLevel::Parent(l) = level {
+                    level = PathLevel::Parent(l + 1);
+                }
+            }
+            "@root" if index == 0 => level = PathLevel::Root,
+            s if s.starts_with('@') => return None,
+            _ => path.push(segment),
+        }
+    }
+
+    Some((level, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_path_test() {
+        assert_eq!(
+            parse_path("database.url"),
+            Some((PathLevel::Parent(0), vec!["database".into(), "url".into()]))
+        );
+        assert_eq!(
+            parse_path("../services.[0]/name"),
+            Some((
+                PathLevel::Parent(1),
+                vec!["services".into(), "0".into(), "name".into()]
+            ))
+        );
+        assert_eq!(
+            parse_path("@root.dogs"),
+            Some((PathLevel::Root, vec!["dogs".into()]))
+        );
+        assert_eq!(parse_path("this"), Some((PathLevel::Parent(0), vec![])));
+        assert_eq!(parse_path("@index"), None);
+    }
+
+    #[test]
+    fn variable_references_test() {
+        let mut registry = handlebars::Handlebars::new();
+        registry
+            .register_template_string(
+                "root",
+                "{{database.url}}{{#if debug}}{{level}}{{/if}}{{#each animals}}{{name}}{{../dogs}}{{/each}}{{> base}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string(
+                "base",
+                "{{#with database as |db|}}{{echo}}{{db.url}}{{/with}}{{> card services.[0]}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string("card", "{{name}}")
+            .unwrap();
+
+        let references: Vec<(String, String, ReferenceKind, usize)> =
+            variable_references(&registry, "root")
+                .into_iter()
+                .map(|r| (r.template.clone(), r.dotted_path(), r.kind, r.guards.len()))
+                .collect();
+        assert_eq!(
+            references,
+            vec![
+                (
+                    "root".into(),
+                    "database.url".into(),
+                    ReferenceKind::Value,
+                    0
+                ),
+                ("root".into(), "debug".into(), ReferenceKind::Condition, 0),
+                ("root".into(), "level".into(), ReferenceKind::Value, 1),
+                ("root".into(), "animals".into(), ReferenceKind::Context, 0),
+                (
+                    "root".into(),
+                    "animals[].name".into(),
+                    ReferenceKind::Value,
+                    0
+                ),
+                ("root".into(), "dogs".into(), ReferenceKind::Value, 0),
+                ("base".into(), "database".into(), ReferenceKind::Context, 0),
+                (
+                    "base".into(),
+                    "database.echo".into(),
+                    ReferenceKind::Value,
+                    0
+                ),
+                (
+                    "base".into(),
+                    "database.url".into(),
+                    ReferenceKind::Value,
+                    0
+                ),
+                (
+                    "base".into(),
+                    "services.0".into(),
+                    ReferenceKind::Context,
+                    0
+                ),
+                (
+                    "card".into(),
+                    "services.0.name".into(),
+                    ReferenceKind::Value,
+                    0
+                ),
+            ]
+        );
+    }
+}